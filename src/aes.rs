@@ -1,5 +1,7 @@
+use anyhow::{bail, Result};
 use openssl::{symm, symm::Cipher};
 
+use crate::gf128::Gf128;
 use crate::padding::Pkcs7Padding;
 use crate::utils::UnicodeUtils;
 
@@ -171,6 +173,100 @@ impl AesEncryption for Aes128Ctr {
     }
 }
 
+pub const GCM_IV_SIZE: usize = 12_usize;
+
+pub struct Aes128Gcm;
+
+impl Aes128Gcm {
+    fn encrypt_block(block: &[u8], key: &[u8]) -> [u8; AES_BLOCK_SIZE] {
+        let mut cipher_block = [0_u8; AES_BLOCK_SIZE];
+        cipher_block.copy_from_slice(&Aes128Ecb::encrypt(block, key, None)[..AES_BLOCK_SIZE]);
+
+        cipher_block
+    }
+
+    /// The authentication key H = E(K, 0^128)
+    pub fn hash_key(key: &[u8]) -> Gf128 {
+        Gf128::from_block(&Self::encrypt_block(&[0; AES_BLOCK_SIZE], key))
+    }
+
+    /// GHASH over the zero padded AAD and cipher text followed by the block of their bit lengths
+    pub fn ghash(h: Gf128, aad: &[u8], cipher_buffer: &[u8]) -> Gf128 {
+        let mut length_block = (aad.len() as u64 * 8).to_be_bytes().to_vec();
+        length_block.extend_from_slice(&(cipher_buffer.len() as u64 * 8).to_be_bytes());
+
+        aad.chunks(AES_BLOCK_SIZE)
+            .chain(cipher_buffer.chunks(AES_BLOCK_SIZE))
+            .chain(std::iter::once(length_block.as_slice()))
+            .fold(Gf128::ZERO, |acc, block| (acc + Gf128::from_block(block)) * h)
+    }
+
+    /// Pre-counter block J0. 96 bit IVs are used directly, others are hashed.
+    fn pre_counter_block(h: Gf128, iv: &[u8]) -> [u8; AES_BLOCK_SIZE] {
+        if iv.len() == GCM_IV_SIZE {
+            let mut j0 = [0_u8; AES_BLOCK_SIZE];
+            j0[..GCM_IV_SIZE].copy_from_slice(iv);
+            j0[AES_BLOCK_SIZE - 1] = 1;
+
+            j0
+        } else {
+            Self::ghash(h, &[], iv).to_block()
+        }
+    }
+
+    /// Increments the rightmost 32 bits of the counter block
+    fn inc32(counter_block: &mut [u8; AES_BLOCK_SIZE]) {
+        let count = u32::from_be_bytes(counter_block[GCM_IV_SIZE..].try_into().unwrap());
+        counter_block[GCM_IV_SIZE..].copy_from_slice(&count.wrapping_add(1).to_be_bytes());
+    }
+
+    fn gctr(buffer: &[u8], key: &[u8], j0: &[u8; AES_BLOCK_SIZE]) -> Vec<u8> {
+        let mut counter_block = *j0;
+
+        buffer
+            .chunks(AES_BLOCK_SIZE)
+            .flat_map(|block| {
+                Self::inc32(&mut counter_block);
+                block.xor_all(&Self::encrypt_block(&counter_block, key))
+            })
+            .collect::<Vec<_>>()
+    }
+
+    fn tag(key: &[u8], h: Gf128, j0: &[u8; AES_BLOCK_SIZE], aad: &[u8], cipher_buffer: &[u8]) -> [u8; AES_BLOCK_SIZE] {
+        let s = Gf128::from_block(&Self::encrypt_block(j0, key));
+
+        (Self::ghash(h, aad, cipher_buffer) + s).to_block()
+    }
+
+    /// Authenticated encryption, returns cipher text and the full 128 bit tag
+    pub fn encrypt(plain_buffer: &[u8], key: &[u8], iv: &[u8], aad: &[u8]) -> (Vec<u8>, [u8; AES_BLOCK_SIZE]) {
+        let h = Self::hash_key(key);
+        let j0 = Self::pre_counter_block(h, iv);
+
+        let cipher_buffer = Self::gctr(plain_buffer, key, &j0);
+        let tag = Self::tag(key, h, &j0, aad, &cipher_buffer);
+
+        (cipher_buffer, tag)
+    }
+
+    /// Authenticated decryption. The tag may be truncated, in which case only its leftmost bytes are compared.
+    pub fn decrypt(cipher_buffer: &[u8], key: &[u8], iv: &[u8], aad: &[u8], tag: &[u8]) -> Result<Vec<u8>> {
+        if tag.is_empty() || tag.len() > AES_BLOCK_SIZE {
+            bail!("Invalid tag length!");
+        }
+
+        let h = Self::hash_key(key);
+        let j0 = Self::pre_counter_block(h, iv);
+
+        let expected_tag = Self::tag(key, h, &j0, aad, cipher_buffer);
+        if expected_tag[..tag.len()] != *tag {
+            bail!("Invalid tag!");
+        }
+
+        Ok(Self::gctr(cipher_buffer, key, &j0))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +321,59 @@ mod tests {
             plain_text.as_bytes()
         );
     }
+
+    #[test]
+    fn test_aes_gcm_nist_vectors() {
+        // Test cases 2, 4 and 6 from the GCM specification (McGrew & Viega)
+        let key = [0_u8; AES_BLOCK_SIZE];
+        let (cipher_buffer, tag) = Aes128Gcm::encrypt(&[0; AES_BLOCK_SIZE], &key, &[0; GCM_IV_SIZE], &[]);
+        assert_eq!(hex::encode(cipher_buffer), "0388dace60b6a392f328c2b971b2fe78");
+        assert_eq!(hex::encode(tag), "ab6e47d42cec13bdf53a67b21257bddf");
+
+        let key = hex::decode("feffe9928665731c6d6a8f9467308308").unwrap();
+        let plain_text = hex::decode(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+            1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+        )
+        .unwrap();
+        let aad = hex::decode("feedfacedeadbeeffeedfacedeadbeefabaddad2").unwrap();
+
+        let iv = hex::decode("cafebabefacedbaddecaf888").unwrap();
+        let (cipher_buffer, tag) = Aes128Gcm::encrypt(&plain_text, &key, &iv, &aad);
+        assert_eq!(
+            hex::encode(&cipher_buffer),
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+            21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091"
+        );
+        assert_eq!(hex::encode(tag), "5bc94fbc3221a5db94fae95ae7121a47");
+        assert_eq!(Aes128Gcm::decrypt(&cipher_buffer, &key, &iv, &aad, &tag).unwrap(), plain_text);
+
+        let iv = hex::decode(
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+            c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+        )
+        .unwrap();
+        let (cipher_buffer, tag) = Aes128Gcm::encrypt(&plain_text, &key, &iv, &aad);
+        assert_eq!(
+            hex::encode(&cipher_buffer),
+            "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca7\
+            01e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5"
+        );
+        assert_eq!(hex::encode(tag), "619cc5aefffe0bfa462af43c1699d050");
+    }
+
+    #[test]
+    fn test_aes_gcm_invalid_tag() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [1_u8; GCM_IV_SIZE];
+        let (mut cipher_buffer, tag) = Aes128Gcm::encrypt(b"HALLO LEGO!!", key, &iv, b"header");
+
+        // Truncated tags are accepted
+        assert!(Aes128Gcm::decrypt(&cipher_buffer, key, &iv, b"header", &tag[..4]).is_ok());
+
+        assert!(Aes128Gcm::decrypt(&cipher_buffer, key, &iv, b"headex", &tag).is_err());
+
+        cipher_buffer[0] ^= 1;
+        assert!(Aes128Gcm::decrypt(&cipher_buffer, key, &iv, b"header", &tag).is_err());
+    }
 }
@@ -1,3 +1,5 @@
 pub mod set1;
 pub mod set2;
 pub mod set3;
+pub mod set7;
+pub mod set8;
@@ -14,6 +14,9 @@ use anyhow::{anyhow, Result};
 use fst::automaton::Levenshtein;
 use fst::{IntoStreamer, Set};
 
+use crate::language_model::LanguageModel;
+use crate::plaintext::PlaintextClassifier;
+
 pub trait PatternScorer {
     fn new() -> Self;
     fn get_score(&self, pattern: &str) -> Result<u16>;
@@ -59,11 +62,12 @@ impl DictionaryScorer {
         }
     }
 
+    /// Saturates rather than overflow on long texts
     pub fn get_score(&self, pattern: &str) -> Result<u16> {
-        let score = pattern
-            .split(' ')
-            .map(|w| self.get_word_score(w))
-            .sum::<Result<u16, _>>()?;
+        let mut score = 0_u16;
+        for word in pattern.split(' ') {
+            score = score.saturating_add(self.get_word_score(word)?);
+        }
 
         Ok(score)
     }
@@ -80,145 +84,220 @@ impl PatternScorer for DictionaryScorer {
     }
 }
 
-impl PatternScorer for Arc<DictionaryScorer> {
-    fn new() -> Self
-    {
-        Arc::new(DictionaryScorer::new())
+/// Averages the frequencies per 10000 bytes of the letters of a text, ignoring case, so that
+/// texts of any length score on the same scale
+pub struct FrequencyScorer {
+    frequencies: [u16; 256],
+}
+
+impl FrequencyScorer {
+    pub fn from_model(model: &LanguageModel) -> Self {
+        let total = model.total(1).max(1);
+        let mut frequencies = [0_u16; 256];
+        for (unigram, &count) in model.counts(1) {
+            let letter = unigram[0].to_ascii_lowercase() as usize;
+            frequencies[letter] += (count * 10000 / total) as u16;
+        }
+
+        Self { frequencies }
+    }
+
+    pub fn get_score(&self, pattern: &str) -> Result<u16> {
+        let total = pattern
+            .bytes()
+            .map(|letter| self.frequencies[letter.to_ascii_lowercase() as usize] as u64)
+            .sum::<u64>();
+
+        // Every frequency is at most 10000, and so is their average
+        Ok((total / pattern.len().max(1) as u64) as u16)
+    }
+}
+
+impl PatternScorer for FrequencyScorer {
+    fn new() -> Self {
+        Self::from_model(&LanguageModel::english().expect("Failed to open corpus file."))
     }
 
     fn get_score(&self, pattern: &str) -> Result<u16> {
-        self.deref().get_score(pattern)
+        self.get_score(pattern)
     }
 }
 
-pub struct FrequencyScorer;
-impl FrequencyScorer
-{
-    pub fn new() -> Self { Self{} }
+/// Pseudo-count given to every byte or n-gram missing from the corpus
+const SMOOTHING: f64 = 0.01;
 
-    pub fn get_score(pattern: &str) -> Result<u16>
-    {
-        let score = pattern
-            .split(' ')
-            .fold(0, |acc, x| acc + Self::get_word_score(x));
+/// Scores a text by the chi-squared statistic of its byte counts against the distribution of a
+/// reference corpus, the lower the statistic the higher the score.
+pub struct ChiSquaredScorer {
+    expected: [f64; 256],
+}
 
-        Ok(score)
+impl ChiSquaredScorer {
+    pub fn from_model(model: &LanguageModel) -> Self {
+        let total = model.total(1) as f64 + 256.0 * SMOOTHING;
+        let mut expected = [SMOOTHING / total; 256];
+        for (unigram, &count) in model.counts(1) {
+            expected[unigram[0] as usize] = (count as f64 + SMOOTHING) / total;
+        }
+
+        Self { expected }
     }
 
-    fn get_word_score(word: &str) -> u16
-    {
-        let score = word.to_lowercase()
-            .as_bytes()
+    /// The chi-squared statistic of the byte counts of `text`, divided by its length so texts of
+    /// different lengths compare
+    pub fn chi_squared(&self, text: &[u8]) -> f64 {
+        let mut counts = [0_usize; 256];
+        for &byte in text {
+            counts[byte as usize] += 1;
+        }
+
+        let length = text.len() as f64;
+        let statistic = counts
             .iter()
-            .fold(0, |acc, x| acc + Self::get_letter_score(x));
+            .zip(self.expected.iter())
+            .map(|(&observed, probability)| {
+                let expected = probability * length;
+                (observed as f64 - expected).powi(2) / expected
+            })
+            .sum::<f64>();
+
+        statistic / length
+    }
 
-        println!("Get score: {word} => {score}");
-        score
+    pub fn get_score(&self, pattern: &str) -> Result<u16> {
+        if pattern.is_empty() {
+            return Ok(0);
+        }
+
+        let chi_squared = self.chi_squared(pattern.as_bytes());
+
+        Ok((u16::MAX as f64 / (1.0 + chi_squared)) as u16)
     }
+}
 
-    fn get_letter_score(letter: &u8) -> u16
-    {
-        // English letter frequency count
-        /*match letter
-        {
-            b'a' => 812,
-            b'b' => 149,
-            b'c' => 271,
-            b'd' => 432,
-            b'e' => 1202,
-            b'f' => 230,
-            b'g' => 203,
-            b'h' => 592,
-            b'i' => 731,
-            b'j' => 10,
-            b'k' => 69,
-            b'l' => 398,
-            b'm' => 261,
-            b'n' => 695,
-            b'o' => 768,
-            b'p' => 182,
-            b'q' => 11,
-            b'r' => 602,
-            b's' => 628,
-            b't' => 910,
-            b'u' => 288,
-            b'v' => 111,
-            b'w' => 209,
-            b'x' => 17,
-            b'y' => 211,
-            b'z' => 7,
-            // Special chars
-            b'.' => 300,
-            b',' => 400,
-            b'\'' => 200,
-            b'/' => 100,
-            b'?' => 200,
-            b'!' => 200,
-            b' ' => 1217,
-            _ => 0,
-        }*/
-        // Letter count from the 19.txt and 20.txt
-        match letter
-        {
-            b' ' => 1135,
-            b'!' => 15,
-            b'"' => 82,
-            b'\'' => 196,
-            b',' => 93,
-            b'-' => 22,
-            b'.' => 15,
-            b'/' => 61,
-            b'4' => 1,
-            b':' => 5,
-            b';' => 6,
-            b'?' => 7,
-            b'\\' => 2,
-            b'a' => 369,
-            b'b' => 168,
-            b'c' => 127,
-            b'd' => 173,
-            b'e' => 584,
-            b'f' => 86,
-            b'g' => 88,
-            b'h' => 273,
-            b'i' => 375,
-            b'j' => 7,
-            b'k' => 63,
-            b'l' => 170,
-            b'm' => 152,
-            b'n' => 310,
-            b'o' => 348,
-            b'p' => 84,
-            b'q' => 4,
-            b'r' => 296,
-            b's' => 303,
-            b't' => 439,
-            b'u' => 171,
-            b'v' => 43,
-            b'w' => 88,
-            b'x' => 5,
-            b'y' => 131,
-            b'z' => 11,
-            _ => 0,
+impl PatternScorer for ChiSquaredScorer {
+    fn new() -> Self {
+        Self::from_model(&LanguageModel::english().expect("Failed to open corpus file."))
+    }
+
+    fn get_score(&self, pattern: &str) -> Result<u16> {
+        self.get_score(pattern)
+    }
+}
+
+/// Scores a text by the average log-likelihood of its n-grams, their log10 probabilities being
+/// taken from a reference corpus
+pub struct NgramScorer {
+    n: usize,
+    log_probabilities: HashMap<Vec<u8>, f64>,
+    /// Log probability of an n-gram missing from the corpus
+    floor: f64,
+}
+
+impl NgramScorer {
+    pub fn from_model(model: &LanguageModel, n: usize) -> Self {
+        let total = model.total(n) as f64;
+        let log_probabilities = model
+            .counts(n)
+            .iter()
+            .map(|(ngram, &count)| (ngram.clone(), (count as f64 / total).log10()))
+            .collect();
+
+        Self {
+            n,
+            log_probabilities,
+            floor: (SMOOTHING / total).log10(),
         }
     }
+
+    pub fn bigrams(model: &LanguageModel) -> Self {
+        Self::from_model(model, 2)
+    }
+
+    pub fn trigrams(model: &LanguageModel) -> Self {
+        Self::from_model(model, 3)
+    }
+
+    pub fn quadgrams(model: &LanguageModel) -> Self {
+        Self::from_model(model, 4)
+    }
+
+    /// Average log10 probability of the n-grams of `text`, None if it is shorter than n
+    pub fn log_likelihood(&self, text: &[u8]) -> Option<f64> {
+        let ngrams = text.windows(self.n);
+        let count = ngrams.len();
+        if count == 0 {
+            return None;
+        }
+
+        let sum = ngrams
+            .map(|ngram| *self.log_probabilities.get(ngram).unwrap_or(&self.floor))
+            .sum::<f64>();
+
+        Some(sum / count as f64)
+    }
+
+    /// The log-likelihood mapped linearly from [floor, 0] onto the range of u16
+    pub fn get_score(&self, pattern: &str) -> Result<u16> {
+        let score = self
+            .log_likelihood(pattern.as_bytes())
+            .map_or(0.0, |likelihood| 1.0 - likelihood / self.floor);
+
+        Ok((score * u16::MAX as f64) as u16)
+    }
 }
 
-impl PatternScorer for FrequencyScorer {
-    fn new() -> Self
-    {
-        Self::new()
+/// Default to quadgrams, the most selective of the n-gram tables
+impl PatternScorer for NgramScorer {
+    fn new() -> Self {
+        Self::quadgrams(&LanguageModel::english().expect("Failed to open corpus file."))
     }
 
     fn get_score(&self, pattern: &str) -> Result<u16> {
-        Self::get_score(pattern)
+        self.get_score(pattern)
     }
 }
 
-impl PatternScorer for Arc<FrequencyScorer> {
-    fn new() -> Self
-    {
-        Arc::new(FrequencyScorer::new())
+/// Averages the chi-squared score with the bigram, trigram and quadgram scores: the byte
+/// distribution settles short texts while the n-grams pick up word structure
+pub struct CombinedScorer {
+    chi_squared: ChiSquaredScorer,
+    ngrams: Vec<NgramScorer>,
+}
+
+impl CombinedScorer {
+    pub fn from_model(model: &LanguageModel) -> Self {
+        Self {
+            chi_squared: ChiSquaredScorer::from_model(model),
+            ngrams: (2..=model.max_order())
+                .map(|n| NgramScorer::from_model(model, n))
+                .collect(),
+        }
+    }
+
+    pub fn get_score(&self, pattern: &str) -> Result<u16> {
+        let mut total = self.chi_squared.get_score(pattern)? as u32;
+        for scorer in &self.ngrams {
+            total += scorer.get_score(pattern)? as u32;
+        }
+
+        Ok((total / (1 + self.ngrams.len() as u32)) as u16)
+    }
+}
+
+impl PatternScorer for CombinedScorer {
+    fn new() -> Self {
+        Self::from_model(&LanguageModel::english().expect("Failed to open corpus file."))
+    }
+
+    fn get_score(&self, pattern: &str) -> Result<u16> {
+        self.get_score(pattern)
+    }
+}
+
+impl<T: PatternScorer> PatternScorer for Arc<T> {
+    fn new() -> Self {
+        Arc::new(T::new())
     }
 
     fn get_score(&self, pattern: &str) -> Result<u16> {
@@ -226,56 +305,116 @@ impl PatternScorer for Arc<FrequencyScorer> {
     }
 }
 
+pub fn decipher_bytes(cipher: &[u8], key: u8) -> Vec<u8> {
+    cipher.iter().map(|c| c ^ key).collect()
+}
+
 fn decipher(cipher: &str, key: u8) -> Result<String> {
-    let iter: Vec<u8> = hex::decode(cipher)?
-        .iter()
-        .cloned()
-        .map(|c| c ^ key)
-        .collect();
+    let deciphered = decipher_bytes(&hex::decode(cipher)?, key);
 
-    Ok(String::from_utf8_lossy(&iter).into_owned())
+    Ok(String::from_utf8_lossy(&deciphered).into_owned())
 }
 
 pub fn break_cipher<T: PatternScorer>(dict: T, cipher: &str) -> Result<Deciphered> {
-    let mut key_score = HashMap::new();
+    break_cipher_with(&dict, cipher)
+}
 
-    for c in 32..=255_u8 // Needed to be from 0 for challenge 19 and 20
-    // All printable chars
-    {
-        if let Ok(deciphered) = decipher(cipher, c) {
-            if deciphered.is_ascii() {
-                let score = dict.get_score(&deciphered).unwrap();
+/// Same as `break_cipher`, judging the candidates with any plaintext classifier
+pub fn break_cipher_with<C: PlaintextClassifier + ?Sized>(
+    classifier: &C,
+    cipher: &str,
+) -> Result<Deciphered> {
+    let best = break_single_byte_xor(classifier, &hex::decode(cipher)?)?;
+
+    Ok(Deciphered {
+        key: best.key,
+        score: best.score,
+        cipher: String::from(cipher),
+        deciphered: String::from_utf8_lossy(&best.plaintext).into_owned(),
+    })
+}
 
-                key_score.insert(c, score);
-            }
-        }
+/// Same as `rank_single_byte_keys` on a hex encoded cipher
+pub fn rank_keys<C: PlaintextClassifier + ?Sized>(
+    classifier: &C,
+    cipher: &str,
+    count: usize,
+) -> Result<Ranking> {
+    rank_single_byte_keys(classifier, &hex::decode(cipher)?, count)
+}
+
+pub fn break_single_byte_xor<C: PlaintextClassifier + ?Sized>(
+    classifier: &C,
+    cipher: &[u8],
+) -> Result<Candidate> {
+    let ranking = rank_single_byte_keys(classifier, cipher, 1)?;
+
+    Ok(ranking.candidates.into_iter().next().unwrap())
+}
+
+/// The `count` best keys, best first, among the ones the classifier gives a positive score
+pub fn rank_single_byte_keys<C: PlaintextClassifier + ?Sized>(
+    classifier: &C,
+    cipher: &[u8],
+    count: usize,
+) -> Result<Ranking> {
+    let mut key_score = (0..=255_u8)
+        .filter_map(|key| {
+            let score = classifier.classify(&decipher_bytes(cipher, key))?;
+            (score > 0).then(|| (key, score))
+        })
+        .collect::<Vec<_>>();
+
+    if key_score.is_empty() {
+        return Err(anyhow!("Unable to break the cipher."));
     }
 
-    //println!("Keyscores: {:?}", key_score);
+    // Highest score first, ties going to the lowest key so the ranking is stable
+    key_score.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let total = key_score.iter().map(|(_, score)| *score as f64).sum::<f64>();
+    let second = key_score.get(1).map_or(0, |(_, score)| *score);
+    let confidence = (key_score[0].1 - second) as f64 / total;
+
+    let candidates = key_score
+        .into_iter()
+        .take(count.max(1))
+        .map(|(key, score)| Candidate {
+            key,
+            score,
+            normalized_score: score as f64 / total,
+            plaintext: decipher_bytes(cipher, key),
+        })
+        .collect();
 
-    let max_score_key = key_score
-        .iter()
-        .max_by(|a, b| a.1.cmp(b.1))
-        .map(|(k, _v)| *k);
+    Ok(Ranking {
+        candidates,
+        confidence,
+    })
+}
 
-    if let Some(max_score_key) = max_score_key {
-        let max_score = key_score[&max_score_key];
+/// A key with its plaintext, the normalized score being its score divided by the sum of the
+/// scores of every accepted key
+#[derive(Debug, PartialEq)]
+pub struct Candidate {
+    pub key: u8,
+    pub score: u16,
+    pub normalized_score: f64,
+    pub plaintext: Vec<u8>,
+}
 
-        if let Ok(deciphered_msg) = decipher(cipher, max_score_key) {
-            if max_score > 0 {
-                println!("Key became: {:?}, {}", max_score_key, max_score);
+/// Candidate keys, best first
+#[derive(Debug, PartialEq)]
+pub struct Ranking {
+    pub candidates: Vec<Candidate>,
+    /// Margin between the normalized scores of the first and second keys, close to zero when
+    /// the best key is hardly better than the next one
+    pub confidence: f64,
+}
 
-                return Ok(Deciphered {
-                    key: max_score_key,
-                    score: max_score,
-                    cipher: String::from(cipher),
-                    deciphered: deciphered_msg,
-                });
-            }
-        }
+impl Ranking {
+    pub fn best(&self) -> &Candidate {
+        &self.candidates[0]
     }
-
-    Err(anyhow!("Unable to break the cipher."))
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -290,6 +429,100 @@ pub struct Deciphered {
 mod tests {
     use super::*;
 
+    const CIPHER: &str = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
+    const ENGLISH: &str = "Now that the party is jumping, with the bass kicked in";
+
+    fn assert_prefers_english<T: PatternScorer>(scorer: &T) {
+        let gibberish = decipher(&hex::encode(ENGLISH), 0x15).unwrap();
+        let flipped_case = decipher(&hex::encode(ENGLISH), 0x20).unwrap();
+        let english = scorer.get_score(ENGLISH).unwrap();
+
+        assert!(english > scorer.get_score(&gibberish).unwrap());
+        assert!(english > scorer.get_score(&flipped_case).unwrap());
+    }
+
+    fn assert_breaks_cipher<T: PatternScorer>(scorer: T) {
+        let deciphered = break_cipher(scorer, CIPHER).unwrap();
+
+        assert_eq!(deciphered.key, b'X');
+        assert_eq!(deciphered.deciphered, "Cooking MC's like a pound of bacon");
+    }
+
+    #[test]
+    fn test_challenge3_rank_keys() {
+        let ranking = rank_keys(&CombinedScorer::new(), CIPHER, 5).unwrap();
+
+        assert_eq!(ranking.candidates.len(), 5);
+        assert_eq!(ranking.best().key, b'X');
+        assert!(ranking
+            .candidates
+            .windows(2)
+            .all(|pair| pair[0].normalized_score >= pair[1].normalized_score));
+        assert!(ranking.confidence > 0.0);
+        assert!(ranking.confidence < ranking.best().normalized_score);
+    }
+
+    #[test]
+    fn test_challenge3_control_byte_key() {
+        let cipher = decipher_bytes(ENGLISH.as_bytes(), 0x03);
+
+        let best = break_single_byte_xor(&CombinedScorer::new(), &cipher).unwrap();
+
+        assert_eq!(best.key, 0x03);
+        assert_eq!(best.plaintext, ENGLISH.as_bytes());
+    }
+
+    #[test]
+    fn test_challenge3_frequency() {
+        let scorer = FrequencyScorer::new();
+        let gibberish = decipher(&hex::encode(ENGLISH), 0x15).unwrap();
+
+        assert!(scorer.get_score(ENGLISH).unwrap() > scorer.get_score(&gibberish).unwrap());
+    }
+
+    #[test]
+    fn test_challenge3_frequency_long_text() {
+        let scorer = FrequencyScorer::new();
+        let english = ENGLISH.repeat(4);
+        let gibberish = decipher(&hex::encode(&english), 0x15).unwrap();
+
+        assert!(english.len() > 100);
+        assert_eq!(scorer.get_score(&english).unwrap(), scorer.get_score(ENGLISH).unwrap());
+        assert!(scorer.get_score(&english).unwrap() > scorer.get_score(&gibberish).unwrap());
+    }
+
+    #[test]
+    fn test_challenge3_chi_squared() {
+        let scorer = ChiSquaredScorer::new();
+
+        assert!(scorer.chi_squared(ENGLISH.as_bytes()) < scorer.chi_squared(b"zzqx jjvk"));
+        assert_prefers_english(&scorer);
+        assert_breaks_cipher(scorer);
+    }
+
+    #[test]
+    fn test_challenge3_ngrams() {
+        let model = LanguageModel::english().unwrap();
+
+        for scorer in [
+            NgramScorer::bigrams(&model),
+            NgramScorer::trigrams(&model),
+            NgramScorer::quadgrams(&model),
+        ] {
+            assert_eq!(scorer.get_score("a").unwrap(), 0);
+            assert_prefers_english(&scorer);
+            assert_breaks_cipher(scorer);
+        }
+    }
+
+    #[test]
+    fn test_challenge3_combined() {
+        let scorer = Arc::new(CombinedScorer::new());
+
+        assert_prefers_english(&scorer);
+        assert_breaks_cipher(scorer);
+    }
+
     #[test]
     fn test_challenge3() {
         let dict = DictionaryScorer::new();
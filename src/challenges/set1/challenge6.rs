@@ -1,60 +1,159 @@
 //! Break repeating-key XOR
 //! <https://cryptopals.com/sets/1/challenges/6>
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use threadpool::ThreadPool;
 
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::fs::read_to_string;
+use std::ops::RangeInclusive;
 use std::sync::mpsc::channel;
 use std::sync::Arc;
 use std::marker::{Send, Sync};
 
 use crate::utils::{UnicodeUtils, Base64};
 
-use super::challenge3::{break_cipher, PatternScorer};
+use crate::plaintext::PlaintextClassifier;
 
-fn normalize_hamming_distance(first: &[u8], second: &[u8]) -> Result<u64> {
+use super::challenge3::{rank_single_byte_keys, PatternScorer, Ranking};
+
+fn normalize_hamming_distance(first: &[u8], second: &[u8]) -> Result<f64> {
     assert_eq!(first.len(), second.len());
     let hamming_distance = hamming::distance_fast(first, second)?;
-    let normalized_hamming_distance = hamming_distance / (first.len() as u64);
+    let normalized_hamming_distance = hamming_distance as f64 / first.len() as f64;
 
     Ok(normalized_hamming_distance)
 }
 
-fn normalize_hamming_distance_on_slices(buffer: &[u8], key_size: usize) -> Result<f64> {
-    let mut slices: Vec<&[u8]> = vec![];
-    for i in 0..4 {
-        let slice = &buffer[i * key_size..(i + 1) * key_size];
-        slices.push(slice);
-    }
-
-    let combinations = slices.iter().tuple_combinations();
-    let count = combinations.clone().count() as f64;
+/// Average normalized Hamming distance between consecutive `key_size` blocks, None when there
+/// are less than two whole blocks
+fn normalize_hamming_distance_on_slices(buffer: &[u8], key_size: usize) -> Option<f64> {
+    let blocks = buffer.chunks_exact(key_size);
+    let count = blocks.len().checked_sub(1).filter(|&count| count > 0)? as f64;
 
-    let distance_sum = combinations
+    let distance_sum = blocks
+        .tuple_windows()
         .map(|(a, b)| normalize_hamming_distance(a, b))
         .map(Result::unwrap)
-        .sum::<u64>();
+        .sum::<f64>();
+
+    Some(distance_sum / count)
+}
+
+/// Probability that two bytes picked at random are equal, around 0.06 for English text against
+/// 1 / 256 for random bytes
+pub fn index_of_coincidence(bytes: &[u8]) -> f64 {
+    if bytes.len() < 2 {
+        return 0.0;
+    }
+
+    let mut counts = [0_usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+
+    let pairs = counts.iter().map(|&n| n * n.saturating_sub(1)).sum::<usize>();
+    pairs as f64 / (bytes.len() * (bytes.len() - 1)) as f64
+}
 
-    //println!("{:?}", distance_sum);
+/// Distances between consecutive occurrences of every repeated trigram
+fn kasiski_distances(buffer: &[u8]) -> Vec<usize> {
+    let mut last_seen = HashMap::new();
 
-    Ok(distance_sum as f64 / count)
+    buffer
+        .windows(3)
+        .enumerate()
+        .filter_map(|(i, trigram)| last_seen.insert(trigram, i).map(|previous| i - previous))
+        .collect()
+}
+
+/// Key sizes tried when none are given, as in the challenge
+pub const DEFAULT_KEY_SIZES: RangeInclusive<usize> = 2..=39;
+
+/// Most likely key sizes `break_xor` tries
+const TRIED_KEY_SIZES: usize = 3;
+
+/// Statistics of a key size over a repeating-key XOR cipher
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySizeCandidate {
+    pub key_size: usize,
+    /// Average normalized Hamming distance between consecutive blocks, the lower the better
+    pub hamming_distance: f64,
+    /// Average index of coincidence of the columns, the higher the better
+    pub index_of_coincidence: f64,
+    /// Share of the distances between repeated trigrams that are multiples of the key size, the
+    /// higher the better
+    pub kasiski: f64,
+}
+
+/// Ranks the key sizes of `key_sizes` spanning at least two blocks of `buffer`, most likely
+/// first. Every statistic ranks the sizes on its own and the sizes are ordered by the sum of
+/// their ranks, ties going to the smallest size as multiples of the key size score as well.
+pub fn rank_key_sizes(buffer: &[u8], key_sizes: RangeInclusive<usize>) -> Vec<KeySizeCandidate> {
+    assert!(*key_sizes.start() > 0, "Key sizes must be positive");
+
+    let distances = kasiski_distances(buffer);
+    let candidates = key_sizes
+        .filter_map(|key_size| {
+            let hamming_distance = normalize_hamming_distance_on_slices(buffer, key_size)?;
+            let columns = (0..key_size)
+                .map(|i| buffer.iter().skip(i).step_by(key_size).copied().collect::<Vec<_>>());
+            let index_of_coincidence =
+                columns.map(|column| index_of_coincidence(&column)).sum::<f64>() / key_size as f64;
+            let multiples = distances.iter().filter(|&&d| d % key_size == 0).count();
+            let kasiski = multiples as f64 / distances.len().max(1) as f64;
+
+            Some(KeySizeCandidate {
+                key_size,
+                hamming_distance,
+                index_of_coincidence,
+                kasiski,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Number of candidates strictly better on a statistic, so that ties share their rank
+    let rank = |candidate: &KeySizeCandidate, statistic: fn(&KeySizeCandidate) -> f64| {
+        let value = statistic(candidate);
+        candidates.iter().filter(|other| statistic(other) > value).count()
+    };
+    let rank_sum = |candidate: &KeySizeCandidate| {
+        rank(candidate, |c| -c.hamming_distance)
+            + rank(candidate, |c| c.index_of_coincidence)
+            + rank(candidate, |c| c.kasiski)
+    };
+
+    candidates
+        .iter()
+        .sorted_by_key(|candidate| (rank_sum(candidate), candidate.key_size))
+        .cloned()
+        .collect()
+}
+
+/// Candidates kept for every key byte, and prefixes of the key kept while extending them
+const BEAM_WIDTH: usize = 3;
+
+/// Judges plaintexts as columns of a repeating key, leaving out the classifiers that only make
+/// sense on whole plaintexts
+struct ColumnClassifier<'a, C: ?Sized>(&'a C);
+
+impl<C: PlaintextClassifier + ?Sized> PlaintextClassifier for ColumnClassifier<'_, C> {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        self.0.classify_column(plaintext)
+    }
 }
 
-pub struct RepeatingKeyXorBreaker<Scorer: PatternScorer> {
+pub struct RepeatingKeyXorBreaker<Scorer: PlaintextClassifier> {
     cipher_buffer: Vec<u8>,
     scorer: Arc<Scorer>,
 }
 
-impl<Scorer: PatternScorer + Send + Sync + 'static> RepeatingKeyXorBreaker<Scorer>
-where Arc<Scorer>: PatternScorer {
+impl<Scorer: PatternScorer + Send + Sync + 'static> RepeatingKeyXorBreaker<Scorer> {
     pub fn new(cipher_buffer: &[u8]) -> Self {
-        Self {
-            cipher_buffer: cipher_buffer.to_owned(),
-            scorer: Arc::new(Scorer::new()),
-        }
+        Self::with_classifier(cipher_buffer, Scorer::new())
     }
 
     fn new_from_file(file_path: &str) -> Self {
@@ -67,21 +166,36 @@ where Arc<Scorer>: PatternScorer {
 
         Self { cipher_buffer, scorer: Arc::new(Scorer::new()) }
     }
+}
 
-    fn get_smallest_average_key_size(&self) -> Option<usize> {
-        (2..40).min_by_key(|i| {
-            OrderedFloat(normalize_hamming_distance_on_slices(&self.cipher_buffer, *i).unwrap())
-        })
+impl<Scorer: PlaintextClassifier + Send + Sync + 'static> RepeatingKeyXorBreaker<Scorer> {
+    /// Judges the columns of the key with `classifier`, for plaintexts other than English text
+    pub fn with_classifier(cipher_buffer: &[u8], classifier: Scorer) -> Self {
+        Self {
+            cipher_buffer: cipher_buffer.to_owned(),
+            scorer: Arc::new(classifier),
+        }
+    }
+
+    /// The key size of `DEFAULT_KEY_SIZES` ranking first
+    fn most_likely_key_size(&self) -> Option<usize> {
+        self.rank_key_sizes(DEFAULT_KEY_SIZES)
+            .first()
+            .map(|candidate| candidate.key_size)
+    }
+
+    pub fn rank_key_sizes(&self, key_sizes: RangeInclusive<usize>) -> Vec<KeySizeCandidate> {
+        rank_key_sizes(&self.cipher_buffer, key_sizes)
     }
 
     fn get_blocks(&self, size: usize) -> Vec<&[u8]> {
         self.cipher_buffer.chunks(size).collect::<Vec<&[u8]>>()
     }
 
-    fn get_transposed_blocks_with_key_size(&self, key_size: usize) -> Vec<String> {
+    fn get_transposed_blocks_with_key_size(&self, key_size: usize) -> Vec<Vec<u8>> {
         let blocks = self.get_blocks(key_size);
 
-        let mut transposed_blocks: Vec<String> = vec![];
+        let mut transposed_blocks: Vec<Vec<u8>> = vec![];
         for i in 0..key_size {
             let mut new_block: Vec<u8> = vec![];
 
@@ -94,13 +208,15 @@ where Arc<Scorer>: PatternScorer {
                 }
             }
 
-            transposed_blocks.push(hex::encode(new_block));
+            transposed_blocks.push(new_block);
         }
 
         transposed_blocks
     }
 
-    pub fn break_blocks_with_key_size(&self, key_size: usize) -> Vec<u8> {
+    /// The best `count` candidates for every byte of the key, an error when the classifier
+    /// accepts no byte for a column
+    fn rank_key_bytes(&self, key_size: usize, count: usize) -> Result<Vec<Ranking>> {
         let (tx, rx) = channel();
         let pool = ThreadPool::new(8);
 
@@ -113,19 +229,65 @@ where Arc<Scorer>: PatternScorer {
             let tx = tx.clone();
 
             pool.execute(move || {
-                let deciphered = break_cipher(scorer, &block);
-
-                println!("Deciphered: {:?}", deciphered);
+                let ranking = rank_single_byte_keys(&ColumnClassifier(&*scorer), &block, count);
 
-                tx.send((i, deciphered)).expect("Unable to send wtf");
+                tx.send((i, ranking)).expect("Unable to send wtf");
             });
         }
 
         rx.iter()
             .take(blocks_num)
             .sorted_by(|a, b| Ord::cmp(&a.0, &b.0))
-            .map(|k| k.1.unwrap().key)
-            .collect::<Vec<_>>()
+            .map(|(i, ranking)| {
+                ranking.map_err(|_| anyhow!("No key byte accepted for column {}", i))
+            })
+            .collect()
+    }
+
+    pub fn break_blocks_with_key_size(&self, key_size: usize) -> Result<Vec<u8>> {
+        self.beam_search_key(key_size, BEAM_WIDTH)
+    }
+
+    /// Builds the key a byte at a time from the `beam_width` best candidates of every byte,
+    /// keeping the `beam_width` prefixes whose bytes have the highest sum of normalized scores on
+    /// their columns, which compare whatever the length of the text. The first key whose whole
+    /// plaintext the classifier accepts is kept, so that classifiers such as file signatures
+    /// judge full plaintexts only.
+    pub fn beam_search_key(&self, key_size: usize, beam_width: usize) -> Result<Vec<u8>> {
+        Ok(self.search_key(key_size, beam_width)?.0)
+    }
+
+    /// Same as `beam_search_key`, along with the average normalized score of the key bytes
+    fn search_key(&self, key_size: usize, beam_width: usize) -> Result<(Vec<u8>, f64)> {
+        let mut beam: Vec<(Vec<u8>, f64)> = vec![(vec![], 0.0)];
+
+        for ranking in self.rank_key_bytes(key_size, beam_width)? {
+            let mut extended = beam
+                .iter()
+                .flat_map(|(prefix, column_score)| {
+                    ranking.candidates.iter().map(move |candidate| {
+                        let mut key = prefix.clone();
+                        key.push(candidate.key);
+                        (key, column_score + candidate.normalized_score)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // The sort is stable, keeping the better prefixes first among equal scores
+            extended.sort_by_key(|(_, column_score)| Reverse(OrderedFloat(*column_score)));
+            extended.truncate(beam_width.max(1));
+            beam = extended;
+        }
+
+        // The whole plaintext is only accepted or ruled out, its score depending on its length
+        // for some scorers
+        beam.into_iter()
+            .find(|(key, _)| {
+                let plaintext = self.cipher_buffer.xor_repeating_key(key);
+                self.scorer.classify(&plaintext).is_some()
+            })
+            .map(|(key, column_score)| (key, column_score / key_size as f64))
+            .ok_or_else(|| anyhow!("No plaintext accepted for key size {}", key_size))
     }
 
     fn decrypt(&self, key: &[u8]) -> String {
@@ -134,25 +296,69 @@ where Arc<Scorer>: PatternScorer {
         String::from_utf8_lossy(xored_bytes).into_owned()
     }
 
-    pub fn break_xor(&self) -> String {
-        let key_size = self.get_smallest_average_key_size()
-            .expect("Key size could not be found.");
+    pub fn break_xor(&self) -> Result<String> {
+        self.break_xor_with_key_sizes(DEFAULT_KEY_SIZES, TRIED_KEY_SIZES)
+    }
 
-        println!("key_size: {:?}", key_size);
+    /// Breaks the cipher with each of the `tries` most likely key sizes of `key_sizes`, keeping
+    /// the plaintext whose key bytes have the highest average normalized score on their columns.
+    /// Key sizes that cannot be broken are passed over, the last error being returned when none
+    /// can.
+    pub fn break_xor_with_key_sizes(
+        &self,
+        key_sizes: RangeInclusive<usize>,
+        tries: usize,
+    ) -> Result<String> {
+        let candidates = self.rank_key_sizes(key_sizes);
+
+        let mut best: Option<(f64, usize, Vec<u8>)> = None;
+        let mut error = anyhow!("Key size could not be found.");
+        for candidate in candidates.iter().take(tries.max(1)) {
+            let (key, score) = match self.search_key(candidate.key_size, BEAM_WIDTH) {
+                Ok(found) => found,
+                Err(e) => {
+                    error = e;
+                    continue;
+                }
+            };
+            let plaintext = self.cipher_buffer.xor_repeating_key(&key);
 
-        self.break_xor_with_key_size(key_size)
+            // Multiples of the key size decipher just as well, so the smaller size wins ties
+            let better = best.as_ref().map_or(true, |(best_score, best_size, _)| {
+                score > *best_score || (score == *best_score && candidate.key_size < *best_size)
+            });
+            if better {
+                best = Some((score, candidate.key_size, plaintext));
+            }
+        }
+
+        let (_, _, plaintext) = best.ok_or(error)?;
+        Ok(String::from_utf8_lossy(&plaintext).into_owned())
     }
 
-    pub fn break_xor_with_key_size(&self, key_size: usize) -> String {
-        let key = self.break_blocks_with_key_size(key_size);
-        self.decrypt(&key)
+    pub fn break_xor_with_key_size(&self, key_size: usize) -> Result<String> {
+        let key = self.break_blocks_with_key_size(key_size)?;
+        Ok(self.decrypt(&key))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::challenges::set1::challenge3::DictionaryScorer;
+    use crate::challenges::set1::challenge3::{ChiSquaredScorer, CombinedScorer, DictionaryScorer};
+    use crate::language_model::{LanguageModel, ENGLISH_CORPUS};
+    use crate::pcg::Pcg32;
+    use crate::plaintext::{ClassifierSet, EntropyClassifier, LanguageClassifier, MagicClassifier};
+    use rand::{Rng, SeedableRng};
+
+    /// The first `length` bytes of the English corpus, and the same encrypted with `key`
+    fn encrypt_corpus(length: usize, key: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let corpus = read_to_string(ENGLISH_CORPUS).unwrap();
+        let plaintext = corpus.as_bytes()[..length].to_vec();
+        let cipher_buffer = plaintext.xor_repeating_key(key);
+
+        (plaintext, cipher_buffer)
+    }
 
     #[test]
     fn test_challenge6_hamming_distance() {
@@ -169,7 +375,7 @@ mod tests {
         assert_eq!(
             normalize_hamming_distance("this is a test".as_bytes(), "wokka wokka!!!".as_bytes())
                 .unwrap(),
-            2
+            37.0 / 14.0
         );
     }
 
@@ -181,19 +387,45 @@ mod tests {
                 14
             )
             .unwrap(),
-            1.3333333333333333
+            37.0 / 14.0
         );
         assert_eq!(
             normalize_hamming_distance_on_slices("hejhejhejhej".as_bytes(), 3).unwrap(),
             0.0
         );
+        assert_eq!(normalize_hamming_distance_on_slices("hejhe".as_bytes(), 3), None);
+    }
+
+    #[test]
+    fn test_challenge6_index_of_coincidence() {
+        assert_eq!(index_of_coincidence(b"aaaa"), 1.0);
+        assert_eq!(index_of_coincidence(b"abcd"), 0.0);
+        assert_eq!(index_of_coincidence(b"aabb"), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn test_challenge6_kasiski_distances() {
+        assert_eq!(kasiski_distances(b"abcXabcYYabc"), [4, 5]);
+    }
+
+    #[test]
+    fn test_challenge6_rank_key_sizes() {
+        let key = b"Netherfield";
+        let (plaintext, cipher_buffer) = encrypt_corpus(3000, key);
+
+        let candidates = rank_key_sizes(&cipher_buffer, DEFAULT_KEY_SIZES);
+        assert_eq!(candidates[0].key_size, key.len());
+
+        let breaker = RepeatingKeyXorBreaker::<CombinedScorer>::new(&cipher_buffer);
+        let deciphered = breaker.break_xor_with_key_sizes(2..=30, TRIED_KEY_SIZES).unwrap();
+        assert_eq!(deciphered.as_bytes(), plaintext);
     }
 
     #[test]
     fn test_challenge6_smallest_normalized_distance() {
         let breaker = RepeatingKeyXorBreaker::<DictionaryScorer>::new_from_file("data/6.txt");
 
-        assert_eq!(breaker.get_smallest_average_key_size(), Some(29));
+        assert_eq!(breaker.most_likely_key_size(), Some(29));
     }
 
     #[test]
@@ -208,7 +440,89 @@ mod tests {
     #[test]
     fn test_challenge6_break_blocks() {
         let breaker = RepeatingKeyXorBreaker::<DictionaryScorer>::new_from_file("data/6.txt");
-        assert_eq!(breaker.break_blocks_with_key_size(breaker.get_smallest_average_key_size().unwrap()), "Terminator X: Bring the noise".as_bytes());
+        assert_eq!(breaker.break_blocks_with_key_size(breaker.most_likely_key_size().unwrap()).unwrap(), "Terminator X: Bring the noise".as_bytes());
+    }
+
+    #[test]
+    fn test_challenge6_break_with_classifier() {
+        let key = b"Pride";
+        let (_, cipher_buffer) = encrypt_corpus(2000, key);
+
+        let english = LanguageClassifier::new(&LanguageModel::english().unwrap());
+        let classifiers = ClassifierSet::new().with(english).with(MagicClassifier);
+        let breaker = RepeatingKeyXorBreaker::with_classifier(&cipher_buffer, classifiers);
+
+        assert_eq!(breaker.break_blocks_with_key_size(key.len()).unwrap(), key);
+    }
+
+    #[test]
+    fn test_challenge6_break_binary() {
+        let key = [0x13, 0x37, 0xc0, 0xff, 0xee];
+        let header = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\x03";
+        let classifiers = || {
+            ClassifierSet::new()
+                .with(MagicClassifier)
+                .with(EntropyClassifier::default())
+        };
+
+        // The columns of a compressed body are too random for any key byte to stand out
+        let mut compressed = header.to_vec();
+        compressed.resize(3000, 0);
+        Pcg32::seed_from_u64(6).fill(&mut compressed[header.len()..]);
+        let cipher_buffer = compressed.xor_repeating_key(&key);
+        let breaker = RepeatingKeyXorBreaker::with_classifier(&cipher_buffer, classifiers());
+        assert!(breaker.break_blocks_with_key_size(key.len()).is_err());
+
+        // Padded with zeros, the body gives the key away, and the signature is checked on the
+        // whole plaintext
+        let mut padded = compressed[..200].to_vec();
+        padded.resize(3000, 0);
+        let cipher_buffer = padded.xor_repeating_key(&key);
+        let breaker = RepeatingKeyXorBreaker::with_classifier(&cipher_buffer, classifiers());
+        let found = breaker.break_blocks_with_key_size(key.len()).unwrap();
+        assert_eq!(found, key);
+        assert_eq!(
+            MagicClassifier::file_type(&cipher_buffer.xor_repeating_key(&found)),
+            Some("gzip")
+        );
+    }
+
+    #[test]
+    fn test_challenge6_beam_search() {
+        let key = b"Netherfield";
+        let (_, cipher_buffer) = encrypt_corpus(1500, key);
+
+        let breaker = RepeatingKeyXorBreaker::<CombinedScorer>::new(&cipher_buffer);
+
+        // A beam of one keeps the best candidate of every byte
+        let greedy = breaker
+            .rank_key_bytes(key.len(), 1)
+            .unwrap()
+            .iter()
+            .map(|ranking| ranking.best().key)
+            .collect::<Vec<_>>();
+        assert_eq!(breaker.beam_search_key(key.len(), 1).unwrap(), greedy);
+        assert_eq!(breaker.beam_search_key(key.len(), BEAM_WIDTH).unwrap(), key);
+    }
+
+    #[test]
+    fn test_challenge6_control_byte_key() {
+        let key = [0x00, 0x1b, 0x7f, 0x0a, 0xff];
+        let (_, cipher_buffer) = encrypt_corpus(1500, &key);
+
+        let breaker = RepeatingKeyXorBreaker::<ChiSquaredScorer>::new(&cipher_buffer);
+
+        assert_eq!(breaker.break_blocks_with_key_size(key.len()).unwrap(), key);
+    }
+
+    #[test]
+    fn test_challenge6_break_combined() {
+        let breaker = RepeatingKeyXorBreaker::<CombinedScorer>::new_from_file("data/6.txt");
+        let key = b"Terminator X: Bring the noise";
+
+        assert_eq!(breaker.most_likely_key_size(), Some(key.len()));
+        assert_eq!(breaker.break_blocks_with_key_size(key.len()).unwrap(), key);
+        assert_eq!(breaker.break_xor().unwrap(), breaker.decrypt(key));
     }
 
     #[test]
@@ -223,7 +537,7 @@ mod tests {
     #[test]
     fn test_challenge6_break() {
         let breaker = RepeatingKeyXorBreaker::<DictionaryScorer>::new_from_file("data/6.txt");
-        let plaintext = breaker.break_xor();
+        let plaintext = breaker.break_xor().unwrap();
 
         println!("{:?}", plaintext);
         assert_eq!(&plaintext[..33], "I'm back and I'm ringin' the bell");
@@ -3,30 +3,29 @@
 
 use crate::aes::{Aes128Cbc, Aes128Ecb, AesEncryption, AES_BLOCK_SIZE};
 use crate::padding::Pkcs7Padding;
-use crate::utils::generate_random_bytes;
+use crate::utils::generate_random_bytes_with_rng;
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-fn encrypt_with_random_key<T: AesEncryption>(plain_buffer: &[u8]) -> Vec<u8> {
-    let key = generate_random_bytes(Some(AES_BLOCK_SIZE));
+fn encrypt_with_random_key<T: AesEncryption, R: Rng>(rng: &mut R, plain_buffer: &[u8]) -> Vec<u8> {
+    let key = generate_random_bytes_with_rng(rng, Some(AES_BLOCK_SIZE));
     T::encrypt(plain_buffer, &key, None)
 }
 
-fn encryption_oracle(plain_buffer: &[u8]) -> (Vec<u8>, bool) {
-    let mut rng = thread_rng();
-
+fn encryption_oracle<R: Rng>(rng: &mut R, plain_buffer: &[u8]) -> (Vec<u8>, bool) {
     let number_of_bytes = rng.gen_range(5..=10);
-    let padding = || generate_random_bytes(Some(number_of_bytes)).with_padding(AES_BLOCK_SIZE);
+    let mut padding =
+        || generate_random_bytes_with_rng(rng, Some(number_of_bytes)).with_padding(AES_BLOCK_SIZE);
 
     let data = padding()
         .into_iter()
         .chain(plain_buffer.iter().cloned())
-        .chain(padding().into_iter())
+        .chain(padding())
         .collect::<Vec<_>>();
 
     match rng.gen_range(0..=1) {
-        0 => (encrypt_with_random_key::<Aes128Cbc>(data.as_slice()), false), // TODO: we need to use a random IV
-        _ => (encrypt_with_random_key::<Aes128Ecb>(data.as_slice()), true),
+        0 => (encrypt_with_random_key::<Aes128Cbc, _>(rng, &data), false), // TODO: we need to use a random IV
+        _ => (encrypt_with_random_key::<Aes128Ecb, _>(rng, &data), true),
     }
 }
 
@@ -36,16 +35,20 @@ mod tests {
 
     use crate::aes::{Aes128Cbc, Aes128Ecb, AES_BLOCK_SIZE};
     use crate::detect::DetectReps;
+    use crate::mersenne_twister::MT19937_64;
+    use rand::{thread_rng, SeedableRng};
 
     #[test]
     fn test_challenge11_detect_ecb_or_cbc() {
         let plain_text = "YELLOW SUBMARINEYELLOW SUBMARINE".as_bytes();
 
+        let mut rng = thread_rng();
+
         for _ in 0..10 {
-            let ecb = encrypt_with_random_key::<Aes128Ecb>(plain_text);
+            let ecb = encrypt_with_random_key::<Aes128Ecb, _>(&mut rng, plain_text);
             assert!(ecb.detect_repetitions(AES_BLOCK_SIZE));
 
-            let cbc = encrypt_with_random_key::<Aes128Cbc>(plain_text);
+            let cbc = encrypt_with_random_key::<Aes128Cbc, _>(&mut rng, plain_text);
             assert!(!cbc.detect_repetitions(AES_BLOCK_SIZE));
         }
     }
@@ -54,9 +57,30 @@ mod tests {
     fn test_challenge11_encryption_oracle() {
         let plain_text = "YELLOW SUBMARINEYELLOW SUBMARINE".as_bytes();
 
+        let mut rng = thread_rng();
+
         for _ in 0..10 {
-            let (cipher, is_ecb) = encryption_oracle(plain_text);
+            let (cipher, is_ecb) = encryption_oracle(&mut rng, plain_text);
+            assert_eq!(cipher.detect_repetitions(AES_BLOCK_SIZE), is_ecb);
+        }
+    }
+
+    #[test]
+    fn test_challenge11_seeded_oracle() {
+        let plain_text = "YELLOW SUBMARINEYELLOW SUBMARINE".as_bytes();
+        let mut rng = MT19937_64::seed_from_u64(11);
+        let mut replay = MT19937_64::seed_from_u64(11);
+
+        let outputs = (0..10)
+            .map(|_| encryption_oracle(&mut rng, plain_text))
+            .collect::<Vec<_>>();
+
+        // Both modes show up and the same seed replays the same keys and choices
+        assert!(outputs.iter().any(|(_, is_ecb)| *is_ecb));
+        assert!(outputs.iter().any(|(_, is_ecb)| !*is_ecb));
+        for (cipher, is_ecb) in outputs {
             assert_eq!(cipher.detect_repetitions(AES_BLOCK_SIZE), is_ecb);
+            assert_eq!(encryption_oracle(&mut replay, plain_text), (cipher, is_ecb));
         }
     }
 }
@@ -4,7 +4,9 @@
 use crate::aes::{Aes128Ecb, AesEncryption, AES_BLOCK_SIZE};
 use crate::oracle::EncryptionOracle;
 use crate::padding::Pkcs7Padding;
-use crate::utils::generate_random_bytes;
+use crate::utils::generate_random_bytes_with_rng;
+
+use rand::{thread_rng, Rng};
 
 struct EncryptionOracle14 {
     key: Vec<u8>,
@@ -29,9 +31,13 @@ impl EncryptionOracle for EncryptionOracle14 {
 
 impl EncryptionOracle14 {
     pub fn new() -> Self {
+        Self::with_rng(&mut thread_rng())
+    }
+
+    pub fn with_rng<R: Rng>(rng: &mut R) -> Self {
         Self {
-            key: generate_random_bytes(Some(AES_BLOCK_SIZE)),
-            random_prefix: generate_random_bytes(None),
+            key: generate_random_bytes_with_rng(rng, Some(AES_BLOCK_SIZE)),
+            random_prefix: generate_random_bytes_with_rng(rng, None),
         }
     }
 
@@ -51,7 +57,9 @@ impl EncryptionOracle14 {
 mod tests {
     use super::*;
     use crate::detect::DetectReps;
+    use crate::mersenne_twister::MT19937_64;
     use crate::oracle::Oracle;
+    use rand::SeedableRng;
 
     #[test]
     fn test_challenge14_check_block_size() {
@@ -72,6 +80,18 @@ mod tests {
         assert!(oracle.encryption_oracle(&s).detect_repetitions(16));
     }
 
+    #[test]
+    fn test_challenge14_seeded_oracle() {
+        let oracle = EncryptionOracle14::with_rng(&mut MT19937_64::seed_from_u64(14));
+        let replay = EncryptionOracle14::with_rng(&mut MT19937_64::seed_from_u64(14));
+
+        assert_eq!(oracle.random_prefix, replay.random_prefix);
+        assert_eq!(
+            oracle.encryption_oracle(b"A"),
+            replay.encryption_oracle(b"A")
+        );
+    }
+
     #[test]
     fn test_challenge14() {
         let mut oracle = Oracle::new(Box::new(EncryptionOracle14::new()));
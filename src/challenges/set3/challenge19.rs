@@ -4,8 +4,49 @@
 
 #[cfg(test)]
 mod tests {
+    use crate::aes::{Aes128, AES_BLOCK_SIZE};
+    use crate::challenges::set1::challenge3::ChiSquaredScorer;
+    use crate::ctr_breaker::FixedNonceCtrBreaker;
+    use crate::utils::{generate_random_bytes, read_lines_from_file, Base64};
+
     #[test]
     fn test_challenge19() {
-        // TODO: Use solution from 20
+        let key = generate_random_bytes(Some(AES_BLOCK_SIZE));
+        let plaintexts = read_lines_from_file("data/19.txt")
+            .into_iter()
+            .map(|line| line.as_bytes().decode_base64())
+            .collect::<Vec<_>>();
+        let ciphertexts = plaintexts
+            .iter()
+            .map(|plaintext| plaintext.encrypt_aes_128_ctr(&key))
+            .collect::<Vec<_>>();
+
+        let mut breaker = FixedNonceCtrBreaker::<ChiSquaredScorer>::new(&ciphertexts);
+        breaker.recover_keystream();
+
+        // The statistics alone get the positions most lines reach, all of them under the crib
+        let recovered = breaker.plaintexts();
+        let well_sampled = |position: usize| breaker.columns()[position].samples >= 20;
+        let (correct, total) = recovered
+            .iter()
+            .zip(&plaintexts)
+            .flat_map(|(recovered, plaintext)| recovered.iter().zip(plaintext).enumerate())
+            .filter(|(position, _)| well_sampled(*position))
+            .fold((0, 0), |(correct, total), (_, (r, p))| {
+                (correct + (r == p) as usize, total + 1)
+            });
+
+        assert!(total > 0);
+        assert!(correct * 10 >= total * 9, "{} of {} bytes", correct, total);
+
+        // The guess of the first line, corrected by hand, gives the start of every other one
+        let crib = b"I have met them at close of day";
+        breaker.apply_crib(0, 0, crib);
+
+        let recovered = breaker.plaintexts();
+        for (recovered, plaintext) in recovered.iter().zip(&plaintexts) {
+            let known = plaintext.len().min(crib.len());
+            assert_eq!(recovered[..known], plaintext[..known]);
+        }
     }
 }
@@ -28,8 +28,8 @@ mod tests {
     use crate::utils::{Base64, generate_random_bytes, read_lines_from_file};
     use crate::aes::{Aes128, AES_BLOCK_SIZE};
 
-    use crate::challenges::set1::challenge3::FrequencyScorer;
-    use crate::challenges::set1::challenge6::RepeatingKeyXorBreaker;
+    use crate::challenges::set1::challenge3::ChiSquaredScorer;
+    use crate::ctr_breaker::FixedNonceCtrBreaker;
 
     use super::*;
 
@@ -42,44 +42,31 @@ mod tests {
     #[test]
     fn test_challenge20() {
         let key = generate_random_bytes(Some(AES_BLOCK_SIZE));
-        let lines = read_lines_from_file("data/20.txt");
-
-        let encrypted_bytes_vec = lines
-             .into_iter()
-             .map(|s| s.decode_base64())
-             .inspect(|x| println!("{:?}", x))
-             .map(|s| s
-                 .into_bytes()
-                 .encrypt_aes_128_ctr(&key))
-             .collect::<Vec<_>>();
-
-        // Debug
-        encrypted_bytes_vec.iter().for_each(|l| {
-            println!("{:?}", l);
-        });
-
-        let min_len = encrypted_bytes_vec
+        let plaintexts = read_lines_from_file("data/20.txt")
+            .into_iter()
+            .map(|line| line.as_bytes().decode_base64())
+            .collect::<Vec<_>>();
+        let ciphertexts = plaintexts
             .iter()
-            .map(|x| x.len())
-            .min()
-            .unwrap();
+            .map(|plaintext| plaintext.encrypt_aes_128_ctr(&key))
+            .collect::<Vec<_>>();
 
-        println!("Min length: {:?}", min_len);
+        let mut breaker = FixedNonceCtrBreaker::<ChiSquaredScorer>::new(&ciphertexts);
+        breaker.recover_keystream();
 
-        let concatenated_ciphers = encrypted_bytes_vec
+        // Every ciphertext is used in full, the positions most of them reach being reliable
+        let recovered = breaker.plaintexts();
+        let well_sampled = |position: usize| breaker.columns()[position].samples >= 20;
+        let (correct, total) = recovered
             .iter()
-            .map(|x| xor_with_keystream(x))
-            //.inspect(|PlainText(x)| println!("HEJ: {:?}", x.len()))
-            .flat_map(|x| x.0[..min_len].to_vec())
-            .collect::<Vec<u8>>();
-
-        let breaker = RepeatingKeyXorBreaker::<FrequencyScorer>::new(&concatenated_ciphers); // TODO: Break with size specified?
-
-        println!("WAT: {:?}", concatenated_ciphers.len());
-        let key_break = breaker.break_xor_with_key_size(53);
-
-        println!("Decipher: {:?}", key_break);
-
-        //println!("{:?}", concatenated_ciphers.encrypt_aes_128_ctr(&key_break));
+            .zip(&plaintexts)
+            .flat_map(|(recovered, plaintext)| recovered.iter().zip(plaintext).enumerate())
+            .filter(|(position, _)| well_sampled(*position))
+            .fold((0, 0), |(correct, total), (_, (r, p))| {
+                (correct + (r == p) as usize, total + 1)
+            });
+
+        assert!(total > 0);
+        assert!(correct * 10 >= total * 9, "{} of {} bytes", correct, total);
     }
 }
@@ -1,6 +1,19 @@
 //! Implement the MT19937 Mersenne Twister RNG
 //! <https://cryptopals.com/sets/3/challenges/21>
 
+// Implementation is found here: crate::mersenne_twister::MT19937
 
-// Implementation is found here: crate::random::MT19937_64
+#[cfg(test)]
+mod tests {
+    use crate::mersenne_twister::MT19937;
 
+    #[test]
+    fn test_challenge21() {
+        // First outputs of the reference mt19937ar.c for init_genrand(5489)
+        let mut rng = MT19937::from_seed(5489);
+
+        assert_eq!(rng.next_u32(), 3499211612);
+        assert_eq!(rng.next_u32(), 581869302);
+        assert_eq!(rng.next_u32(), 3890346734);
+    }
+}
@@ -1,39 +1,70 @@
 //! Crack an MT19937 seed
 //! <https://cryptopals.com/sets/3/challenges/22>
 
+use crate::clock::Clock;
 use crate::mersenne_twister::MT19937_64;
 
-use std::{thread, time::{Duration, SystemTime}};
+use std::time::Duration;
 use rand::{thread_rng, Rng};
 
-fn get_random_int() -> u64 {
+/// How far back we look for a timestamp used as seed
+pub(super) const MAX_SEED_AGE: u64 = 2000;
+const SEARCH_THREADS: usize = 8;
+
+/// Finds the timestamp within `max_age` seconds before `now` seeding a generator whose first
+/// output is `output`
+pub(super) fn find_timestamp_seed(now: u64, max_age: u64, output: u64) -> Option<u64> {
+    MT19937_64::search_seed(output, 0, now.saturating_sub(max_age)..=now, SEARCH_THREADS)
+}
+
+fn get_random_int<C: Clock>(clock: &C) -> u64 {
     let mut rng = thread_rng();
-    thread::sleep(Duration::from_secs(rng.gen_range(40..1000)));
+    clock.sleep(Duration::from_secs(rng.gen_range(40..1000)));
 
-    let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+    let seed = clock.now();
     let mut mt1 = MT19937_64::from_seed(seed);
 
-    thread::sleep(Duration::from_secs(rng.gen_range(40..1000)));
+    clock.sleep(Duration::from_secs(rng.gen_range(40..1000)));
 
     mt1.next_u64()
 }
 
-fn exploit() -> Option<u64> {
-    let random_int = get_random_int();
-
-    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+fn exploit<C: Clock>(clock: &C) -> Option<u64> {
+    let random_int = get_random_int(clock);
 
-    ((current_time-2000)..current_time).find(|&seed| MT19937_64::from_seed(seed).next_u64() == random_int)
+    find_timestamp_seed(clock.now(), MAX_SEED_AGE, random_int)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{SimulatedClock, SystemClock};
+
+    #[test]
+    fn test_challenge22_find_timestamp_seed() {
+        let now = SystemClock.now();
+        let seed = now - thread_rng().gen_range(0..MAX_SEED_AGE);
+        let random_int = MT19937_64::from_seed(seed).next_u64();
+
+        let found = find_timestamp_seed(now, MAX_SEED_AGE, random_int);
+
+        assert_eq!(found, Some(seed));
+    }
 
-    #[ignore]
     #[test]
     fn test_challenge22() {
-        let exploited_seed = exploit();
+        let clock = SimulatedClock::starting_now();
+        let start = clock.now();
+
+        let exploited_seed = exploit(&clock).unwrap();
+
+        assert!((start + 40..start + 1000).contains(&exploited_seed));
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge22_real_time() {
+        let exploited_seed = exploit(&SystemClock);
         assert!(exploited_seed.is_some());
         println!("Exploited seed: {}", &exploited_seed.unwrap());
     }
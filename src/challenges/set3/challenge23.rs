@@ -1,81 +1,83 @@
 //! Clone an MT19937 RNG from its output
 //! <https://cryptopals.com/sets/3/challenges/23>
 
-use crate::mersenne_twister::{MT19937_64, U, D, S, B, T, C, L, N};
+use crate::mersenne_twister::MT19937_64;
 
-fn temper(y: u64) -> u64 {
-    let mut y = y;
-    y ^= (y >> U) & D;
-    y ^= (y << S) & B;
-    y ^= (y << T) & C;
-    y ^= y >> L;
+/// Untempers the next N outputs, giving the state array they were taken from
+fn get_state(mt: &mut MT19937_64) -> [u64; MT19937_64::N] {
+    let mut state = [0; MT19937_64::N];
 
-    y
-}
-
-fn inv_rs(u: u64, k: u64) -> u64 {
-    assert!(k >= 1);
-    let mut u = u;
-    let mut v = u;
-
-    for _ in 0..=64 / k {
-        u >>= k;
-        v ^= u
-    }
-    v
-}
-
-fn inv_lsa(u: u64, k: u64, c: u64) -> u64 {
-    assert!(k >= 1);
-    let mut v = u;
-
-    for _ in 0..64 / k {
-        v = u ^ (v << k & c);
-    }
-    v
-}
-
-fn inv_lsa2(u: u64, k: u64, c: u64) -> u64 {
-    assert!(k >= 1);
-    let mut v = u;
-
-    for _ in 0..64 / k {
-        v = u ^ (v >> k & c);
-    }
-    v
-}
-
-fn untemper(y: u64) -> u64 {
-    inv_lsa2(inv_lsa(inv_lsa(inv_rs(y, L), T, C), S, B), U, D)
-}
-
-fn get_state(mt: &mut MT19937_64) -> [u64; N] {
-    let mut state: [u64; N] = [0; N];
-
-    state.iter_mut().for_each(|s| *s = (*mt).next_u64());
+    state.iter_mut().for_each(|s| *s = MT19937_64::untemper(mt.next_u64()));
 
     state
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mersenne_twister::MT19937;
+    use rand::{thread_rng, Rng};
 
     #[test]
     fn test_challenge23_untemper() {
-        assert_eq!(14514284786278117030, temper(untemper(14514284786278117030)));
+        assert_eq!(
+            14514284786278117030,
+            MT19937_64::temper(MT19937_64::untemper(14514284786278117030))
+        );
+        assert_eq!(3499211612, MT19937::temper(MT19937::untemper(3499211612)));
     }
 
     #[test]
     fn test_challenge23_splice() {
-        let mut mt = MT19937_64::new();
+        let mut mt = MT19937_64::from_seed(thread_rng().gen());
         let state = get_state(&mut mt);
 
         let mut mt_cloned = MT19937_64::new_with_state(state);
 
-        for _ in 0..N-1 {
-            assert_ne!(mt_cloned.next_u64(), mt.next_u64());
+        for _ in 0..2 * MT19937_64::N {
+            assert_eq!(mt_cloned.next_u64(), mt.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_challenge23_clone_from_outputs() {
+        // Starting in the middle of a twist
+        let mut mt = MT19937::from_seed(thread_rng().gen());
+        (0..100).for_each(|_| {
+            mt.next_u32();
+        });
+
+        let outputs = (0..MT19937::N).map(|_| mt.next_u32()).collect::<Vec<_>>();
+        let mut mt_cloned = MT19937::clone_from_outputs(outputs).unwrap();
+
+        for _ in 0..2 * MT19937::N {
+            assert_eq!(mt_cloned.next_u32(), mt.next_u32());
         }
+
+        assert!(MT19937::clone_from_outputs(vec![0; MT19937::N - 1]).is_none());
+    }
+
+    fn test_clone_from_masked_outputs(mask: u64, count: usize) {
+        let mut mt = MT19937_64::from_seed(thread_rng().gen());
+        let outputs = (0..count).map(|_| mt.next_u64() & mask).collect::<Vec<_>>();
+
+        let mut mt_cloned = MT19937_64::clone_from_masked_outputs(&outputs, mask).unwrap();
+
+        for _ in 0..2 * MT19937_64::N {
+            assert_eq!(mt_cloned.next_u64(), mt.next_u64());
+        }
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge23_clone_from_top_halves() {
+        test_clone_from_masked_outputs(0xFFFF_FFFF_0000_0000, 2 * MT19937_64::N + 16);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge23_clone_from_top_bytes() {
+        // Some state bits take more than a dozen twists to reach the tempered top byte
+        test_clone_from_masked_outputs(0xFF00_0000_0000_0000, 16 * MT19937_64::N);
     }
 }
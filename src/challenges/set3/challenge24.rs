@@ -0,0 +1,149 @@
+//! Create the MT19937 stream cipher and break it
+//! <https://cryptopals.com/sets/3/challenges/24>
+
+use rand::{thread_rng, Rng, RngCore};
+
+use super::challenge22::{find_timestamp_seed, MAX_SEED_AGE};
+use crate::clock::Clock;
+use crate::mersenne_twister::MT19937_64;
+use crate::utils::generate_random_bytes;
+
+const RESET_TOKEN_SIZE: usize = 16;
+
+/// Stream cipher whose keystream is the output of MT19937-64 seeded with a 16 bit key
+pub struct MtCipher {
+    mt: MT19937_64,
+}
+
+impl MtCipher {
+    pub fn new(seed: u16) -> Self {
+        Self {
+            mt: MT19937_64::from_seed(seed as u64),
+        }
+    }
+
+    pub fn keystream(&mut self, size: usize) -> Vec<u8> {
+        let mut keystream = vec![0; size];
+        self.mt.fill_bytes(&mut keystream);
+
+        keystream
+    }
+
+    pub fn apply_keystream(&mut self, buffer: &[u8]) -> Vec<u8> {
+        let keystream = self.keystream(buffer.len());
+
+        buffer.iter().zip(keystream).map(|(x, k)| x ^ k).collect()
+    }
+
+    pub fn encrypt(plain_buffer: &[u8], seed: u16) -> Vec<u8> {
+        Self::new(seed).apply_keystream(plain_buffer)
+    }
+
+    pub fn decrypt(cipher_buffer: &[u8], seed: u16) -> Vec<u8> {
+        Self::new(seed).apply_keystream(cipher_buffer)
+    }
+}
+
+/// Encrypts a random prefix followed by the attacker's plaintext under a random seed
+struct EncryptionOracle24 {
+    seed: u16,
+}
+
+impl EncryptionOracle24 {
+    pub fn new() -> Self {
+        Self {
+            seed: thread_rng().gen(),
+        }
+    }
+
+    pub fn encrypt(&self, plain_buffer: &[u8]) -> Vec<u8> {
+        let prefix_len = thread_rng().gen_range(0..64);
+        let data = generate_random_bytes(Some(prefix_len))
+            .into_iter()
+            .chain(plain_buffer.iter().cloned())
+            .collect::<Vec<_>>();
+
+        MtCipher::encrypt(&data, self.seed)
+    }
+}
+
+/// Only 2^16 keys, so try them all until the known end of the plaintext shows up
+fn recover_seed(cipher_buffer: &[u8], known_suffix: &[u8]) -> Option<u16> {
+    if known_suffix.len() > cipher_buffer.len() {
+        return None;
+    }
+    let offset = cipher_buffer.len() - known_suffix.len();
+
+    (0..=u16::MAX).find(|&seed| {
+        let keystream = MtCipher::new(seed).keystream(cipher_buffer.len());
+
+        cipher_buffer[offset..]
+            .iter()
+            .zip(&keystream[offset..])
+            .map(|(c, k)| c ^ k)
+            .eq(known_suffix.iter().cloned())
+    })
+}
+
+/// Token drawn from MT19937-64 seeded with the current time
+fn reset_token<C: Clock>(clock: &C) -> Vec<u8> {
+    let mut token = vec![0; RESET_TOKEN_SIZE];
+    MT19937_64::from_seed(clock.now()).fill_bytes(&mut token);
+
+    token
+}
+
+/// Whether the token came from a generator seeded with a timestamp at most `MAX_SEED_AGE` seconds,
+/// about half an hour, before the current time. The seed is searched for from the first output,
+/// the first 8 bytes of the token, and the rest of the token confirms it.
+fn is_time_seeded_token<C: Clock>(clock: &C, token: &[u8]) -> bool {
+    let first_output = match token.get(..8) {
+        Some(bytes) => u64::from_le_bytes(bytes.try_into().unwrap()),
+        None => return false,
+    };
+
+    find_timestamp_seed(clock.now(), MAX_SEED_AGE, first_output).map_or(false, |seed| {
+        let mut keystream = vec![0; token.len()];
+        MT19937_64::from_seed(seed).fill_bytes(&mut keystream);
+
+        keystream == token
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use std::time::Duration;
+
+    #[test]
+    fn test_challenge24_cipher() {
+        let plain_buffer = b"YELLOW SUBMARINE, now in a stream cipher";
+        let cipher_buffer = MtCipher::encrypt(plain_buffer, 1234);
+
+        assert_ne!(cipher_buffer, plain_buffer);
+        assert_eq!(MtCipher::decrypt(&cipher_buffer, 1234), plain_buffer);
+    }
+
+    #[test]
+    fn test_challenge24_recover_seed() {
+        let oracle = EncryptionOracle24::new();
+        let known = b"AAAAAAAAAAAAAA";
+
+        let seed = recover_seed(&oracle.encrypt(known), known);
+
+        assert_eq!(seed, Some(oracle.seed));
+    }
+
+    #[test]
+    fn test_challenge24_reset_token() {
+        let clock = SimulatedClock::starting_now();
+
+        let token = reset_token(&clock);
+        clock.sleep(Duration::from_secs(thread_rng().gen_range(0..MAX_SEED_AGE)));
+        assert!(is_time_seeded_token(&clock, &token));
+
+        let token = generate_random_bytes(Some(RESET_TOKEN_SIZE));
+        assert!(!is_time_seeded_token(&clock, &token));
+    }
+}
@@ -0,0 +1,209 @@
+//! RC4 Single-Byte Biases
+//! <https://cryptopals.com/sets/7/challenges/56>
+
+use anyhow::{bail, Result};
+use rand::{thread_rng, RngCore};
+use threadpool::ThreadPool;
+
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use crate::oracle::EncryptionOracle;
+use crate::rc4::Rc4;
+
+/// RC4 keystream byte 16 (index 15) is biased towards 0xF0
+const Z16_INDEX: usize = 15;
+const Z16_BIAS: u8 = 0xF0;
+
+/// RC4 keystream byte 32 (index 31) is biased towards 0xE0
+const Z32_INDEX: usize = 31;
+const Z32_BIAS: u8 = 0xE0;
+
+const RC4_KEY_SIZE: usize = 16;
+
+struct EncryptionOracle56 {
+    cookie: Vec<u8>,
+}
+
+impl EncryptionOracle for EncryptionOracle56 {
+    /// Encrypts `request || cookie` under a fresh random key for every call
+    fn encryption_oracle(&self, plain_buffer: &[u8]) -> Vec<u8> {
+        let mut key = [0_u8; RC4_KEY_SIZE];
+        thread_rng().fill_bytes(&mut key);
+
+        let data = plain_buffer
+            .iter()
+            .chain(self.cookie.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Rc4::encrypt(&data, &key)
+    }
+}
+
+impl EncryptionOracle56 {
+    pub fn new() -> Self {
+        Self {
+            cookie: base64::decode(Self::magic_string()).unwrap(),
+        }
+    }
+
+    const fn magic_string() -> &'static str {
+        "QkUgU1VSRSBUTyBEUklOSyBZT1VSIE9WQUxUSU5F"
+    }
+
+    fn cookie_len(&self) -> usize {
+        self.encryption_oracle(&[]).len()
+    }
+}
+
+/// Byte frequencies of the ciphertext at the Z16 and Z32 positions
+struct BiasCounts {
+    z16: [u64; 256],
+    z32: [u64; 256],
+}
+
+impl BiasCounts {
+    fn new() -> Self {
+        Self {
+            z16: [0; 256],
+            z32: [0; 256],
+        }
+    }
+
+    fn add(&mut self, cipher_buffer: &[u8]) {
+        if let Some(&c) = cipher_buffer.get(Z16_INDEX) {
+            self.z16[c as usize] += 1;
+        }
+        if let Some(&c) = cipher_buffer.get(Z32_INDEX) {
+            self.z32[c as usize] += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.z16.iter_mut().zip(other.z16.iter()).for_each(|(a, b)| *a += b);
+        self.z32.iter_mut().zip(other.z32.iter()).for_each(|(a, b)| *a += b);
+    }
+
+    /// The most frequent ciphertext byte is most likely the plaintext byte xored with the bias
+    fn most_likely_plain(counts: &[u64; 256], bias: u8) -> u8 {
+        let most_frequent = (0..=255_u8).max_by_key(|&c| counts[c as usize]).unwrap();
+
+        most_frequent ^ bias
+    }
+}
+
+pub struct Rc4BiasBreaker {
+    oracle: Arc<EncryptionOracle56>,
+    trials: usize,
+    threads: usize,
+}
+
+impl Rc4BiasBreaker {
+    fn new(oracle: EncryptionOracle56, trials: usize) -> Self {
+        Self {
+            oracle: Arc::new(oracle),
+            trials,
+            threads: 8,
+        }
+    }
+
+    /// Encrypts `prefix_size` padding bytes in front of the cookie `trials` times, spread over a thread pool
+    fn count_with_prefix(&self, prefix_size: usize) -> BiasCounts {
+        let (tx, rx) = channel();
+        let pool = ThreadPool::new(self.threads);
+
+        let trials_per_job = self.trials / self.threads;
+        for _ in 0..self.threads {
+            let oracle = self.oracle.clone();
+            let tx = tx.clone();
+
+            pool.execute(move || {
+                let prefix = vec![b'A'; prefix_size];
+                let mut counts = BiasCounts::new();
+
+                for _ in 0..trials_per_job {
+                    counts.add(&oracle.encryption_oracle(&prefix));
+                }
+
+                tx.send(counts).expect("Unable to send counts");
+            });
+        }
+
+        rx.iter()
+            .take(self.threads)
+            .fold(BiasCounts::new(), |mut acc, counts| {
+                acc.merge(&counts);
+                acc
+            })
+    }
+
+    /// Shifts every cookie byte onto index 15 or 31 of the keystream and recovers it from the
+    /// biases. A prefix only pushes bytes forward, so cookies longer than 32 bytes are out of reach.
+    pub fn break_cookie(&self) -> Result<Vec<u8>> {
+        let cookie_len = self.oracle.cookie_len();
+        if cookie_len > Z32_INDEX + 1 {
+            bail!("Cookies longer than {} bytes cannot be recovered", Z32_INDEX + 1);
+        }
+
+        let mut cookie = vec![0_u8; cookie_len];
+
+        for prefix_size in 0..=Z16_INDEX {
+            let counts = self.count_with_prefix(prefix_size);
+
+            let z16_position = Z16_INDEX - prefix_size;
+            if z16_position < cookie_len {
+                cookie[z16_position] = BiasCounts::most_likely_plain(&counts.z16, Z16_BIAS);
+            }
+
+            let z32_position = Z32_INDEX - prefix_size;
+            if z32_position > Z16_INDEX && z32_position < cookie_len {
+                cookie[z32_position] = BiasCounts::most_likely_plain(&counts.z32, Z32_BIAS);
+            }
+        }
+
+        Ok(cookie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge56_oracle() {
+        let oracle = EncryptionOracle56::new();
+
+        assert_eq!(oracle.cookie_len(), 30);
+        // Fresh keys, so the same request should never encrypt the same twice
+        assert_ne!(oracle.encryption_oracle(b"A"), oracle.encryption_oracle(b"A"));
+    }
+
+    #[test]
+    fn test_challenge56_most_likely_plain() {
+        let mut counts = BiasCounts::new();
+        counts.add(&[0; 32]);
+        counts.add(&[b'B' ^ Z16_BIAS; 32]);
+        counts.add(&[b'B' ^ Z16_BIAS; 32]);
+
+        assert_eq!(BiasCounts::most_likely_plain(&counts.z16, Z16_BIAS), b'B');
+    }
+
+    #[test]
+    fn test_challenge56_long_cookie() {
+        let oracle = EncryptionOracle56 { cookie: vec![b'A'; 33] };
+        let breaker = Rc4BiasBreaker::new(oracle, 1);
+
+        assert!(breaker.break_cookie().is_err());
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge56() {
+        let breaker = Rc4BiasBreaker::new(EncryptionOracle56::new(), 1 << 24);
+        let cookie = breaker.break_cookie().unwrap();
+
+        println!("Cookie: {:?}", String::from_utf8_lossy(&cookie));
+        assert_eq!(cookie, b"BE SURE TO DRINK YOUR OVALTINE");
+    }
+}
@@ -0,0 +1 @@
+mod challenge56;
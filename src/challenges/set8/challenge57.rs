@@ -0,0 +1,171 @@
+//! Diffie-Hellman Revisited: Small Subgroup Confinement
+//! <https://cryptopals.com/sets/8/challenges/57>
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::diffie_hellman::{DhGroup, DhKeyPair};
+use crate::dlog::Group;
+use crate::mac::{derive_key, hmac_sha256};
+use crate::number_theory::{crt, random_in_range, small_prime_factors};
+
+const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+/// Largest subgroup order we are willing to brute force
+pub const SMALL_FACTOR_BOUND: u64 = 1 << 16;
+
+/// Bob answers every key exchange with a message authenticated under the shared key
+pub struct DhOracle57 {
+    group: DhGroup,
+    key_pair: DhKeyPair,
+}
+
+impl DhOracle57 {
+    pub fn new(group: DhGroup) -> Self {
+        let key_pair = DhKeyPair::generate(&group);
+
+        Self { group, key_pair }
+    }
+
+    pub fn group(&self) -> &DhGroup {
+        &self.group
+    }
+
+    pub fn public_key(&self) -> &BigUint {
+        &self.key_pair.public
+    }
+
+    /// Returns (message, mac) without checking that the peer's key has order q
+    pub fn respond(&self, peer_public: &BigUint) -> (Vec<u8>, Vec<u8>) {
+        let shared = self.key_pair.shared_secret(&self.group, peer_public);
+
+        (MESSAGE.to_vec(), mac(&shared, MESSAGE))
+    }
+
+    /// Only for verifying attacks
+    pub(crate) fn secret(&self) -> &BigUint {
+        self.key_pair.secret()
+    }
+}
+
+fn mac(shared: &BigUint, message: &[u8]) -> Vec<u8> {
+    hmac_sha256(&derive_key(&shared.to_bytes_be()), message)
+}
+
+/// Confines the shared secret to subgroups of small prime order r dividing (p - 1) / q, and brute forces the
+/// secret modulo every r from the returned MAC
+pub struct SubgroupConfinementAttack<'a> {
+    oracle: &'a DhOracle57,
+}
+
+impl<'a> SubgroupConfinementAttack<'a> {
+    pub fn new(oracle: &'a DhOracle57) -> Self {
+        Self { oracle }
+    }
+
+    fn group(&self) -> &DhGroup {
+        self.oracle.group()
+    }
+
+    /// Distinct primes below `bound` dividing the cofactor (p - 1) / q
+    pub fn small_factors(&self, bound: u64) -> Vec<u64> {
+        let group = self.group();
+        let cofactor = (&group.p - 1_u32) / &group.q;
+
+        small_prime_factors(&cofactor, bound)
+            .into_iter()
+            .map(|(r, _)| r)
+            .collect()
+    }
+
+    /// Finds an element of order r, which must divide p - 1
+    fn find_element_of_order(&self, r: u64) -> BigUint {
+        let group = self.group();
+        let exponent = (&group.p - 1_u32) / r;
+
+        loop {
+            let h = group.scalar(&random_in_range(&BigUint::from(2_u32), &group.p), &exponent);
+            if !h.is_one() {
+                return h;
+            }
+        }
+    }
+
+    /// Recovers the secret modulo the subgroup order r
+    pub fn recover_residue(&self, r: u64) -> Option<u64> {
+        let group = self.group();
+        let h = self.find_element_of_order(r);
+        let (message, mac_buffer) = self.oracle.respond(&h);
+
+        let mut shared = BigUint::one();
+        for k in 0..r {
+            if mac(&shared, &message) == mac_buffer {
+                return Some(k);
+            }
+            shared = group.operate(&shared, &h);
+        }
+
+        None
+    }
+
+    /// Collects residues over the small subgroups until their product exceeds q or the factors below `bound`
+    /// run out. Returns (residue, modulus) recombined with CRT.
+    pub fn recover_residues(&self, bound: u64) -> Option<(BigUint, BigUint)> {
+        let q = &self.group().q;
+
+        let mut congruences: Vec<(BigUint, BigUint)> = vec![];
+        let mut modulus_product = BigUint::one();
+
+        for r in self.small_factors(bound) {
+            let residue = self.recover_residue(r)?;
+            congruences.push((BigUint::from(residue), BigUint::from(r)));
+
+            modulus_product *= r;
+            if modulus_product > *q {
+                break;
+            }
+        }
+
+        Some(crt(&congruences))
+    }
+
+    pub fn recover_secret(&self) -> Option<BigUint> {
+        let q = &self.group().q;
+        let (residue, modulus) = self.recover_residues(SMALL_FACTOR_BOUND)?;
+
+        (modulus > *q).then(|| residue % q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+
+    #[test]
+    fn test_challenge57_residue() {
+        let oracle = DhOracle57::new(DhGroup::cryptopals_57());
+        let attack = SubgroupConfinementAttack::new(&oracle);
+
+        let residue = attack.recover_residue(109).unwrap();
+
+        assert_eq!((oracle.secret() % 109_u32).to_u64(), Some(residue));
+    }
+
+    #[test]
+    fn test_challenge57() {
+        let group = DhGroup::cryptopals_57();
+        let oracle = DhOracle57::new(group.clone());
+
+        // Sanity check of the honest protocol
+        let alice = DhKeyPair::generate(&group);
+        let (message, mac_buffer) = oracle.respond(&alice.public);
+        assert_eq!(mac(&alice.shared_secret(&group, oracle.public_key()), &message), mac_buffer);
+
+        let attack = SubgroupConfinementAttack::new(&oracle);
+        let secret = attack.recover_secret().unwrap();
+
+        assert_eq!(&secret, oracle.secret());
+        assert_eq!(group.pow_g(&secret), *oracle.public_key());
+    }
+}
@@ -0,0 +1,75 @@
+//! Pollard's Method for Catching Kangaroos
+//! <https://cryptopals.com/sets/8/challenges/58>
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use super::challenge57::{DhOracle57, SubgroupConfinementAttack, SMALL_FACTOR_BOUND};
+use crate::dlog::{kangaroo, Group};
+
+/// The small subgroups of (p - 1) / q no longer cover q, so the residue is finished off with kangaroo
+struct KangarooAttack<'a> {
+    oracle: &'a DhOracle57,
+}
+
+impl<'a> KangarooAttack<'a> {
+    pub fn new(oracle: &'a DhOracle57) -> Self {
+        Self { oracle }
+    }
+
+    /// With x = n (mod r), x = n + m * r and y * g^-n = (g^r)^m where m is in [0, (q - 1) / r]
+    pub fn recover_secret(&self) -> Option<BigUint> {
+        let group = self.oracle.group();
+        let (residue, modulus) = SubgroupConfinementAttack::new(self.oracle).recover_residues(SMALL_FACTOR_BOUND)?;
+
+        let target = group.operate(self.oracle.public_key(), &group.pow_g(&(&group.q - &residue % &group.q)));
+        let generator = group.pow_g(&modulus);
+        let upper = (&group.q - 1_u32) / &modulus;
+
+        let m = kangaroo(group, &generator, &target, &BigUint::zero(), &upper)?;
+
+        Some(residue + m * modulus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diffie_hellman::DhGroup;
+    use crate::number_theory::random_in_range;
+
+    fn test_kangaroo_interval(bits: u32) {
+        let group = DhGroup::cryptopals_58();
+
+        let upper = BigUint::from(1_u64 << bits);
+        let secret = random_in_range(&BigUint::zero(), &upper);
+        let y = group.pow_g(&secret);
+
+        let found = kangaroo(&group, &group.g, &y, &BigUint::zero(), &upper);
+
+        assert_eq!(found, Some(secret));
+    }
+
+    #[test]
+    fn test_challenge58_kangaroo() {
+        test_kangaroo_interval(20);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge58_kangaroo_40_bits() {
+        test_kangaroo_interval(40);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge58() {
+        let group = DhGroup::cryptopals_58();
+        let oracle = DhOracle57::new(group.clone());
+
+        let secret = KangarooAttack::new(&oracle).recover_secret().unwrap();
+
+        assert_eq!(&secret, oracle.secret());
+        assert_eq!(group.pow_g(&secret), *oracle.public_key());
+    }
+}
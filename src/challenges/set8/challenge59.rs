@@ -0,0 +1,187 @@
+//! Elliptic Curve Diffie-Hellman and Invalid-Curve Attacks
+//! <https://cryptopals.com/sets/8/challenges/59>
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+use crate::elliptic_curve::{EcGroup, EcdhKeyPair, Point, WeierstrassCurve};
+use crate::mac::{derive_key, hmac_sha256};
+use crate::number_theory::{crt, small_prime_factors};
+
+const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+/// Largest subgroup order we are willing to brute force
+const SMALL_FACTOR_BOUND: u64 = 1 << 16;
+
+/// Bob answers every key exchange with a message authenticated under the shared key
+struct EcdhOracle59 {
+    group: EcGroup,
+    key_pair: EcdhKeyPair,
+}
+
+impl EcdhOracle59 {
+    pub fn new(group: EcGroup) -> Self {
+        let key_pair = EcdhKeyPair::generate(&group);
+
+        Self { group, key_pair }
+    }
+
+    pub fn public_key(&self) -> &Point {
+        &self.key_pair.public
+    }
+
+    /// Returns (message, mac) without checking that the peer's point is on the curve
+    pub fn respond(&self, peer_public: &Point) -> (Vec<u8>, Vec<u8>) {
+        let shared = self.key_pair.shared_secret(&self.group, peer_public);
+        let key = derive_key(&shared.to_bytes());
+
+        (MESSAGE.to_vec(), hmac_sha256(&key, MESSAGE))
+    }
+}
+
+/// A curve sharing a and p with the real curve, together with its order
+pub struct InvalidCurve {
+    pub curve: WeierstrassCurve,
+    pub order: BigUint,
+}
+
+/// Finds a point of prime order `r` on `curve` which has order `curve_order`
+fn find_point_of_order(curve: &WeierstrassCurve, curve_order: &BigUint, r: u64) -> Point {
+    let cofactor = curve_order / r;
+
+    loop {
+        let point = curve.scalar_mul(&curve.random_point(), &cofactor);
+        if point != Point::Infinity {
+            return point;
+        }
+    }
+}
+
+/// Walks the subgroup generated by `point` until the MAC matches, giving the secret modulo the order r
+fn brute_force_residue(
+    curve: &WeierstrassCurve,
+    point: &Point,
+    r: u64,
+    message: &[u8],
+    mac: &[u8],
+) -> Option<u64> {
+    let mut shared = Point::Infinity;
+
+    for k in 0..r {
+        if hmac_sha256(&derive_key(&shared.to_bytes()), message) == mac {
+            return Some(k);
+        }
+        shared = curve.add(&shared, point);
+    }
+
+    None
+}
+
+struct InvalidCurveAttack<'a> {
+    oracle: &'a EcdhOracle59,
+    invalid_curves: Vec<InvalidCurve>,
+}
+
+impl<'a> InvalidCurveAttack<'a> {
+    pub fn new(oracle: &'a EcdhOracle59, invalid_curves: Vec<InvalidCurve>) -> Self {
+        Self { oracle, invalid_curves }
+    }
+
+    /// Collects residues of the secret over small subgroups of the invalid curves until the moduli cover the
+    /// group order, and combines them with CRT
+    pub fn recover_secret(&self) -> Option<BigUint> {
+        let order = &self.oracle.group.order;
+
+        let mut congruences: Vec<(BigUint, BigUint)> = vec![];
+        let mut modulus_product = BigUint::from(1_u32);
+
+        for invalid_curve in self.invalid_curves.iter() {
+            let factors = small_prime_factors(&invalid_curve.order, SMALL_FACTOR_BOUND);
+
+            for (r, _) in factors.into_iter().filter(|&(r, _)| r > 2) {
+                if congruences.iter().any(|(_, m)| m.to_u64() == Some(r)) {
+                    continue;
+                }
+
+                let point = find_point_of_order(&invalid_curve.curve, &invalid_curve.order, r);
+                let (message, mac) = self.oracle.respond(&point);
+
+                let residue = brute_force_residue(&invalid_curve.curve, &point, r, &message, &mac)?;
+                congruences.push((BigUint::from(residue), BigUint::from(r)));
+
+                modulus_product *= r;
+                if modulus_product > *order {
+                    let (secret, _) = crt(&congruences);
+                    return Some(secret % order);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn cryptopals_invalid_curves(group: &EcGroup) -> Vec<InvalidCurve> {
+    [
+        (210_u32, "233970423115425145550826547352470124412"),
+        (504, "233970423115425145544350131142039591210"),
+        (727, "233970423115425145545378039958152057148"),
+    ]
+    .iter()
+    .map(|&(b, order)| InvalidCurve {
+        curve: group.curve.with_b(BigUint::from(b)),
+        order: BigUint::parse_bytes(order.as_bytes(), 10).unwrap(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::Zero;
+
+    #[test]
+    fn test_challenge59_invalid_curve_orders() {
+        let group = EcGroup::cryptopals_p128();
+
+        for invalid_curve in cryptopals_invalid_curves(&group) {
+            let point = invalid_curve.curve.random_point();
+
+            assert!(!group.curve.contains(&point));
+            assert_eq!(invalid_curve.curve.scalar_mul(&point, &invalid_curve.order), Point::Infinity);
+        }
+    }
+
+    #[test]
+    fn test_challenge59_residue() {
+        let group = EcGroup::cryptopals_p128();
+        let oracle = EcdhOracle59::new(group.clone());
+        let invalid_curve = &cryptopals_invalid_curves(&group)[0];
+
+        let r = 89;
+        let point = find_point_of_order(&invalid_curve.curve, &invalid_curve.order, r);
+        let (message, mac) = oracle.respond(&point);
+
+        let residue = brute_force_residue(&invalid_curve.curve, &point, r, &message, &mac).unwrap();
+        assert!((oracle.key_pair.secret() - residue) % r == BigUint::zero());
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge59() {
+        let group = EcGroup::cryptopals_p128();
+        let oracle = EcdhOracle59::new(group.clone());
+
+        // Sanity check of the honest protocol
+        let alice = EcdhKeyPair::generate(&group);
+        let (message, mac) = oracle.respond(&alice.public);
+        let shared = alice.shared_secret(&group, oracle.public_key());
+        assert_eq!(hmac_sha256(&derive_key(&shared.to_bytes()), &message), mac);
+
+        let attack = InvalidCurveAttack::new(&oracle, cryptopals_invalid_curves(&group));
+        let secret = attack.recover_secret().unwrap();
+
+        assert_eq!(&secret, oracle.key_pair.secret());
+        assert_eq!(group.scalar_base_mul(&secret), *oracle.public_key());
+    }
+}
@@ -0,0 +1,220 @@
+//! Single-Coordinate Ladders and Insecure Twists
+//! <https://cryptopals.com/sets/8/challenges/60>
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::dlog::kangaroo;
+use crate::elliptic_curve::{EcGroup, MontgomeryGroup, MontgomeryKeyPair, Point};
+use crate::mac::{derive_key, hmac_sha256};
+use crate::number_theory::{crt, small_prime_factors};
+
+const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+/// Largest twist subgroup order we are willing to brute force
+const SMALL_FACTOR_BOUND: u64 = 1 << 22;
+
+/// Bob answers every x-only key exchange with a message authenticated under the shared key
+struct EcdhOracle60 {
+    group: MontgomeryGroup,
+    key_pair: MontgomeryKeyPair,
+}
+
+impl EcdhOracle60 {
+    pub fn new(group: MontgomeryGroup) -> Self {
+        let key_pair = MontgomeryKeyPair::generate(&group);
+
+        Self { group, key_pair }
+    }
+
+    pub fn public_key(&self) -> &BigUint {
+        &self.key_pair.public
+    }
+
+    /// Returns (message, mac) without checking that u is on the curve rather than on its twist
+    pub fn respond(&self, peer_public: &BigUint) -> (Vec<u8>, Vec<u8>) {
+        let shared = self.key_pair.shared_secret(&self.group, peer_public);
+
+        (MESSAGE.to_vec(), mac(&shared, MESSAGE))
+    }
+}
+
+fn mac(shared: &BigUint, message: &[u8]) -> Vec<u8> {
+    hmac_sha256(&derive_key(&shared.to_bytes_be()), message)
+}
+
+/// k = ±residue (mod modulus), x-only arithmetic cannot tell k from -k
+#[derive(Debug, Clone, PartialEq)]
+struct SignedResidue {
+    residue: BigUint,
+    modulus: BigUint,
+}
+
+struct TwistAttack<'a> {
+    oracle: &'a EcdhOracle60,
+}
+
+impl<'a> TwistAttack<'a> {
+    pub fn new(oracle: &'a EcdhOracle60) -> Self {
+        Self { oracle }
+    }
+
+    fn group(&self) -> &MontgomeryGroup {
+        &self.oracle.group
+    }
+
+    /// Finds the u coordinate of a twist point of order `order`, which must be a squarefree divisor of the
+    /// twist order
+    fn find_twist_point_of_order(&self, order: &BigUint, prime_factors: &[u64]) -> BigUint {
+        let curve = &self.group().curve;
+        let cofactor = self.group().twist_order() / order;
+
+        loop {
+            let u = curve.ladder(&curve.random_twist_u(), &cofactor);
+
+            let has_full_order = prime_factors
+                .iter()
+                .all(|r| !curve.ladder(&u, &(order / *r)).is_zero());
+            if has_full_order {
+                return u;
+            }
+        }
+    }
+
+    /// Recovers the secret modulo the twist subgroup order r, up to sign
+    fn recover_residue(&self, r: u64) -> Option<SignedResidue> {
+        let curve = &self.group().curve;
+        let u = self.find_twist_point_of_order(&BigUint::from(r), &[r]);
+        let (message, mac_buffer) = self.oracle.respond(&u);
+
+        // u(kP) = u(-kP), so only k in [0, r/2] needs to be tried
+        std::iter::once(BigUint::zero())
+            .chain(curve.multiples(&u))
+            .take((r / 2 + 1) as usize)
+            .position(|shared| mac(&shared, &message) == mac_buffer)
+            .map(|k| SignedResidue {
+                residue: BigUint::from(k),
+                modulus: BigUint::from(r),
+            })
+    }
+
+    /// Combines k = ±n1 (mod r1) and k = ±n2 (mod r2) into k = ±n (mod r1 r2), by querying a point of order
+    /// r1 r2 and testing which of the two relative signs matches
+    fn combine(&self, first: &SignedResidue, second: &SignedResidue, prime_factors: &[u64]) -> Option<SignedResidue> {
+        let curve = &self.group().curve;
+        let modulus = &first.modulus * &second.modulus;
+
+        let u = self.find_twist_point_of_order(&modulus, prime_factors);
+        let (message, mac_buffer) = self.oracle.respond(&u);
+
+        let negated = (&second.modulus - &second.residue) % &second.modulus;
+        let candidates = [second.residue.clone(), negated].map(|residue| {
+            crt(&[
+                (first.residue.clone(), first.modulus.clone()),
+                (residue, second.modulus.clone()),
+            ])
+            .0
+        });
+
+        candidates
+            .into_iter()
+            .find(|k| mac(&curve.ladder(&u, k), &message) == mac_buffer)
+            .map(|residue| SignedResidue { residue, modulus })
+    }
+
+    /// Collects the secret modulo the small factors of the twist order
+    pub fn recover_twist_residue(&self) -> Option<SignedResidue> {
+        let factors = small_prime_factors(&self.group().twist_order(), SMALL_FACTOR_BOUND)
+            .into_iter()
+            .filter(|&(r, _)| r > 2)
+            .map(|(r, _)| r)
+            .collect::<Vec<_>>();
+
+        let mut combined: Option<SignedResidue> = None;
+        for (i, &r) in factors.iter().enumerate() {
+            let residue = self.recover_residue(r)?;
+
+            combined = Some(match combined {
+                None => residue,
+                Some(combined) => self.combine(&combined, &residue, &factors[..=i])?,
+            });
+        }
+
+        combined
+    }
+
+    /// Finishes with kangaroo on the equivalent Weierstrass curve.
+    /// Bob's public key lifts to ±Y, and k = ±n + m * r with m in [0, q / r].
+    pub fn recover_secret(&self) -> Option<BigUint> {
+        let SignedResidue { residue, modulus } = self.recover_twist_residue()?;
+
+        let weierstrass = EcGroup::cryptopals_p128();
+        let curve = &weierstrass.curve;
+        let order = &weierstrass.order;
+
+        let x = self.group().curve.to_weierstrass_x(self.oracle.public_key());
+        let y = curve.lift_x(&x)?;
+
+        let jump_base = weierstrass.scalar_base_mul(&modulus);
+        let upper = order / &modulus;
+
+        for public in [y.clone(), curve.negate(&y)] {
+            for offset in [residue.clone(), &modulus - &residue] {
+                // public - offset * G = m * (r * G)
+                let target = curve.add(&public, &curve.negate(&weierstrass.scalar_base_mul(&offset)));
+                if target == Point::Infinity {
+                    return Some(offset);
+                }
+
+                if let Some(m) = kangaroo(curve, &jump_base, &target, &BigUint::zero(), &upper) {
+                    return Some((offset + m * &modulus) % order);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge60_twist_order() {
+        let group = MontgomeryGroup::cryptopals_p128();
+        let u = group.curve.random_twist_u();
+
+        assert!(!group.curve.contains(&u));
+        assert!(group.curve.ladder(&u, &group.twist_order()).is_zero());
+    }
+
+    #[test]
+    fn test_challenge60_residues() {
+        let oracle = EcdhOracle60::new(MontgomeryGroup::cryptopals_p128());
+        let attack = TwistAttack::new(&oracle);
+        let secret = oracle.key_pair.secret();
+
+        let first = attack.recover_residue(11).unwrap();
+        let second = attack.recover_residue(107).unwrap();
+        let combined = attack.combine(&first, &second, &[11, 107]).unwrap();
+
+        let expected = secret % &combined.modulus;
+        assert!(combined.residue == expected || &combined.modulus - &combined.residue == expected);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge60() {
+        let group = MontgomeryGroup::cryptopals_p128();
+        let oracle = EcdhOracle60::new(group.clone());
+        let attack = TwistAttack::new(&oracle);
+
+        let secret = attack.recover_secret().unwrap();
+
+        // k and q - k are indistinguishable, both give the same public key and shared secrets
+        assert_eq!(group.curve.ladder(&group.base_u, &secret), *oracle.public_key());
+        let expected = oracle.key_pair.secret();
+        assert!(secret == *expected || secret == &group.order - expected);
+    }
+}
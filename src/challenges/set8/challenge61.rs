@@ -0,0 +1,206 @@
+//! Duplicate-Signature Key Selection in ECDSA (and RSA)
+//! <https://cryptopals.com/sets/8/challenges/61>
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use crate::diffie_hellman::DhGroup;
+use crate::dlog::pohlig_hellman;
+use crate::elliptic_curve::{ecdsa_hash, EcGroup, EcdsaKeyPair, EcdsaSignature, Point};
+use crate::number_theory::{crt, is_probable_prime, mod_inverse, random_in_range, small_primes};
+use crate::rsa::{message_representative, RsaKeyPair, RsaPublicKey};
+
+/// Bound on the prime factors of p - 1 for the RSA primes we generate
+const SMOOTHNESS_BOUND: u64 = 1 << 16;
+
+/// Picks a new generator G' and key pair (d', Q' = d' G') under which `signature` on `message` verifies.
+/// Verification checks that u1 G + u2 Q has x coordinate r, so we need u1 G' + u2 d' G' = R for the
+/// R = u1 G + u2 Q of the original key, giving G' = R / (u1 + u2 d').
+pub fn ecdsa_duplicate_key(
+    group: &EcGroup,
+    public: &Point,
+    message: &[u8],
+    signature: &EcdsaSignature,
+) -> Option<(EcGroup, EcdsaKeyPair)> {
+    let n = &group.order;
+    let s_inverse = mod_inverse(&signature.s, n)?;
+    let u1 = ecdsa_hash(group, message) * &s_inverse % n;
+    let u2 = &signature.r * &s_inverse % n;
+
+    let r_point = group.curve.add(&group.scalar_base_mul(&u1), &group.curve.scalar_mul(public, &u2));
+
+    loop {
+        let secret = random_in_range(&BigUint::one(), n);
+        let t = (&u1 + &u2 * &secret) % n;
+        if t.is_zero() {
+            continue;
+        }
+
+        let generator = group.curve.scalar_mul(&r_point, &mod_inverse(&t, n)?);
+        let forged_group = EcGroup {
+            curve: group.curve.clone(),
+            generator,
+            order: n.clone(),
+        };
+        let key_pair = EcdsaKeyPair::from_secret(&forged_group, secret);
+
+        return Some((forged_group, key_pair));
+    }
+}
+
+/// A prime p such that p - 1 = 2 * r_1 * ... * r_k with distinct small odd primes r_i
+struct SmoothPrime {
+    p: BigUint,
+    factors: Vec<u64>,
+}
+
+impl SmoothPrime {
+    /// Multiplicative group modulo p with a generator of order p - 1
+    fn group(&self, generator: &BigUint) -> DhGroup {
+        DhGroup::new(self.p.clone(), generator % &self.p, &self.p - 1_u32)
+    }
+
+    fn is_primitive_root(&self, g: &BigUint) -> bool {
+        let p_minus_one = &self.p - 1_u32;
+
+        !(g % &self.p).is_zero()
+            && self
+                .factors
+                .iter()
+                .all(|&r| !g.modpow(&(&p_minus_one / r), &self.p).is_one())
+    }
+
+    /// Solves base^x = target (mod p) with Pohlig-Hellman, possible as p - 1 is smooth
+    fn discrete_log(&self, base: &BigUint, target: &BigUint) -> Option<BigUint> {
+        let group = self.group(base);
+        let order_factors = self.factors.iter().map(|&r| (r, 1)).collect::<Vec<_>>();
+
+        pohlig_hellman(&group, &group.g, &(target % &self.p), &order_factors).map(|(x, _)| x)
+    }
+}
+
+/// Random smooth prime of at least `bits` bits avoiding the factors in `excluded`
+fn random_smooth_prime(bits: u64, excluded: &[u64]) -> SmoothPrime {
+    let mut rng = thread_rng();
+    let pool = small_primes(SMOOTHNESS_BOUND)
+        .into_iter()
+        .filter(|r| *r > 2 && !excluded.contains(r))
+        .collect::<Vec<_>>();
+
+    loop {
+        let mut factors = vec![2];
+        let mut p_minus_one = BigUint::from(2_u32);
+        while p_minus_one.bits() < bits {
+            let r = *pool.choose(&mut rng).unwrap();
+            if !factors.contains(&r) {
+                factors.push(r);
+                p_minus_one *= r;
+            }
+        }
+
+        let p = p_minus_one + 1_u32;
+        if is_probable_prime(&p) {
+            return SmoothPrime { p, factors };
+        }
+    }
+}
+
+/// Finds a smooth prime p for which the signature is a primitive root and the discrete log of the message
+/// representative is odd. Returns p and that discrete log.
+fn find_signing_prime(bits: u64, excluded: &[u64], signature: &BigUint, hash: &BigUint) -> (SmoothPrime, BigUint) {
+    loop {
+        let prime = random_smooth_prime(bits, excluded);
+        if !prime.is_primitive_root(signature) {
+            continue;
+        }
+
+        if let Some(exponent) = prime.discrete_log(signature, hash) {
+            if exponent.bit(0) {
+                return (prime, exponent);
+            }
+        }
+    }
+}
+
+/// Builds a new RSA key (N', e', d') under which `signature` on `message` verifies, i.e. s^e' = H(m) (mod N').
+/// With p - 1 and q - 1 smooth, e' is found modulo p - 1 and q - 1 separately and recombined with CRT.
+/// Both discrete logs are odd, so they agree modulo 2, the only common factor of p - 1 and q - 1.
+pub fn rsa_duplicate_key(public: &RsaPublicKey, message: &[u8], signature: &BigUint) -> RsaKeyPair {
+    let hash = message_representative(message) % &public.n;
+    // N' must exceed N so that neither the signature nor the hash get reduced
+    let bits = public.n.bits() / 2 + 2;
+
+    loop {
+        let (p, e_p) = find_signing_prime(bits, &[], signature, &hash);
+        let (q, e_q) = find_signing_prime(bits, &p.factors, signature, &hash);
+
+        let half_q_minus_one = (&q.p - 1_u32) >> 1;
+        let (e, _) = crt(&[(e_p, &p.p - 1_u32), (&e_q % &half_q_minus_one, half_q_minus_one)]);
+
+        if let Some(key_pair) = RsaKeyPair::from_primes(&p.p, &q.p, e) {
+            return key_pair;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elliptic_curve::ecdsa_verify;
+
+    const MESSAGE: &[u8] = b"I am Satoshi Nakamoto";
+
+    #[test]
+    fn test_challenge61_ecdsa() {
+        let group = EcGroup::cryptopals_p128();
+        let key_pair = EcdsaKeyPair::generate(&group);
+        let signature = key_pair.sign(&group, MESSAGE);
+        assert!(ecdsa_verify(&group, &key_pair.public, MESSAGE, &signature));
+
+        let (forged_group, forged_key_pair) =
+            ecdsa_duplicate_key(&group, &key_pair.public, MESSAGE, &signature).unwrap();
+
+        assert_ne!(forged_key_pair.public, key_pair.public);
+        assert!(ecdsa_verify(&forged_group, &forged_key_pair.public, MESSAGE, &signature));
+
+        // The attacker owns the new private key
+        let new_signature = forged_key_pair.sign(&forged_group, b"another message");
+        assert!(ecdsa_verify(&forged_group, &forged_key_pair.public, b"another message", &new_signature));
+        assert_eq!(
+            forged_group.scalar_base_mul(forged_key_pair.secret()),
+            forged_key_pair.public
+        );
+    }
+
+    #[test]
+    fn test_challenge61_smooth_prime() {
+        let prime = random_smooth_prime(128, &[3, 5]);
+        let product: BigUint = prime.factors.iter().product();
+
+        assert!(prime.p.bits() >= 128);
+        assert_eq!(product, &prime.p - 1_u32);
+        assert!(!prime.factors.contains(&3) && !prime.factors.contains(&5));
+
+        let generator = (2_u32..).map(BigUint::from).find(|g| prime.is_primitive_root(g)).unwrap();
+        let secret = random_in_range(&BigUint::one(), &(&prime.p - 1_u32));
+        let target = prime.group(&generator).pow_g(&secret);
+        assert_eq!(prime.discrete_log(&generator, &target), Some(secret));
+    }
+
+    #[test]
+    fn test_challenge61_rsa() {
+        let key_pair = RsaKeyPair::generate(512);
+        let signature = key_pair.sign(MESSAGE);
+        assert!(key_pair.public.verify(MESSAGE, &signature));
+
+        let forged_key_pair = rsa_duplicate_key(&key_pair.public, MESSAGE, &signature);
+
+        assert_ne!(forged_key_pair.public, key_pair.public);
+        assert!(forged_key_pair.public.verify(MESSAGE, &signature));
+
+        let new_signature = forged_key_pair.sign(b"another message");
+        assert!(forged_key_pair.public.verify(b"another message", &new_signature));
+    }
+}
@@ -0,0 +1,101 @@
+//! Key-Recovery Attacks on ECDSA with Biased Nonces
+//! <https://cryptopals.com/sets/8/challenges/62>
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::elliptic_curve::{ecdsa_hash, EcGroup, EcdsaKeyPair, EcdsaSignature, Point};
+use crate::lattice::solve_hidden_number_problem;
+use crate::number_theory::{mod_inverse, random_in_range};
+
+/// Signs with nonces whose lowest `bias_bits` bits are always zero
+struct BiasedNonceSigner {
+    group: EcGroup,
+    key_pair: EcdsaKeyPair,
+    bias_bits: u32,
+}
+
+impl BiasedNonceSigner {
+    pub fn new(group: EcGroup, bias_bits: u32) -> Self {
+        let key_pair = EcdsaKeyPair::generate(&group);
+
+        Self {
+            group,
+            key_pair,
+            bias_bits,
+        }
+    }
+
+    pub fn public_key(&self) -> &Point {
+        &self.key_pair.public
+    }
+
+    pub fn sign(&self, message: &[u8]) -> EcdsaSignature {
+        let upper = &self.group.order >> self.bias_bits;
+
+        loop {
+            let nonce = random_in_range(&BigUint::one(), &upper) << self.bias_bits;
+            if let Some(signature) = self.key_pair.sign_with_nonce(&self.group, message, &nonce) {
+                return signature;
+            }
+        }
+    }
+}
+
+/// With k = 2^l b, s = (H(m) + r d) / k gives b = d r / (s 2^l) + H(m) / (s 2^l) (mod q), where b < q / 2^l.
+/// So d t - u is small for t = r / (s 2^l) and u = -H(m) / (s 2^l).
+fn hidden_number_sample(
+    group: &EcGroup,
+    message: &[u8],
+    signature: &EcdsaSignature,
+    bias_bits: u32,
+) -> (BigUint, BigUint) {
+    let q = &group.order;
+    let scale = mod_inverse(&((&signature.s << bias_bits) % q), q).unwrap();
+
+    let t = &signature.r * &scale % q;
+    let u = (q - ecdsa_hash(group, message) * &scale % q) % q;
+
+    (t, u)
+}
+
+/// Collects `count` signatures and solves the resulting hidden number problem for the private key
+fn recover_private_key(signer: &BiasedNonceSigner, count: usize) -> Option<BigUint> {
+    let group = &signer.group;
+
+    let samples = (0..count)
+        .map(|i| {
+            let message = format!("message number {}", i).into_bytes();
+            let signature = signer.sign(&message);
+            hidden_number_sample(group, &message, &signature, signer.bias_bits)
+        })
+        .collect::<Vec<_>>();
+
+    solve_hidden_number_problem(&samples, &group.order, signer.bias_bits)
+        .into_iter()
+        .find(|d| group.scalar_base_mul(d) == *signer.public_key())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_challenge62_heavily_biased() {
+        let signer = BiasedNonceSigner::new(EcGroup::cryptopals_p128(), 48);
+
+        let secret = recover_private_key(&signer, 4).unwrap();
+
+        assert_eq!(&secret, signer.key_pair.secret());
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge62() {
+        let signer = BiasedNonceSigner::new(EcGroup::cryptopals_p128(), 8);
+
+        let secret = recover_private_key(&signer, 22).unwrap();
+
+        assert_eq!(&secret, signer.key_pair.secret());
+    }
+}
@@ -0,0 +1,107 @@
+//! Key-Recovery Attacks on GCM with Repeated Nonces
+//! <https://cryptopals.com/sets/8/challenges/63>
+
+use crate::aes::{Aes128Gcm, AES_BLOCK_SIZE};
+use crate::gf128::{Gf128, Gf128Polynomial};
+
+/// A GCM message as seen on the wire
+#[derive(Debug, Clone)]
+pub struct GcmMessage {
+    pub aad: Vec<u8>,
+    pub cipher_buffer: Vec<u8>,
+    pub tag: [u8; AES_BLOCK_SIZE],
+}
+
+impl GcmMessage {
+    /// The GHASH blocks in order of evaluation, i.e. the coefficients of the highest powers of H first
+    fn blocks(&self) -> Vec<Gf128> {
+        let mut length_block = (self.aad.len() as u64 * 8).to_be_bytes().to_vec();
+        length_block.extend_from_slice(&(self.cipher_buffer.len() as u64 * 8).to_be_bytes());
+
+        self.aad
+            .chunks(AES_BLOCK_SIZE)
+            .chain(self.cipher_buffer.chunks(AES_BLOCK_SIZE))
+            .chain(std::iter::once(length_block.as_slice()))
+            .map(Gf128::from_block)
+            .collect()
+    }
+
+    /// The polynomial g(x) = b_1 x^m + ... + b_m x + t, which evaluates to the mask s = E(K, J0) at x = H
+    fn tag_polynomial(&self) -> Gf128Polynomial {
+        let mut coefficients = vec![Gf128::from_block(&self.tag)];
+        coefficients.extend(self.blocks().into_iter().rev());
+
+        Gf128Polynomial::new(coefficients)
+    }
+}
+
+/// Two messages under the same key and nonce share the mask s, so g_1(H) + g_2(H) = 0.
+/// Returns the candidates for the authentication key H.
+pub fn recover_hash_key_candidates(first: &GcmMessage, second: &GcmMessage) -> Vec<Gf128> {
+    (first.tag_polynomial() + second.tag_polynomial()).roots()
+}
+
+/// Computes a valid tag for arbitrary AAD and cipher text, given H and a message with the reused nonce
+pub fn forge_tag(h: Gf128, known: &GcmMessage, aad: &[u8], cipher_buffer: &[u8]) -> [u8; AES_BLOCK_SIZE] {
+    let s = known.tag_polynomial().evaluate(h);
+
+    (Aes128Gcm::ghash(h, aad, cipher_buffer) + s).to_block()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::{Aes128Ecb, AesEncryption, GCM_IV_SIZE};
+    use crate::utils::{generate_random_bytes, UnicodeUtils};
+
+    fn encrypt(plain_buffer: &[u8], key: &[u8], iv: &[u8], aad: &[u8]) -> GcmMessage {
+        let (cipher_buffer, tag) = Aes128Gcm::encrypt(plain_buffer, key, iv, aad);
+
+        GcmMessage {
+            aad: aad.to_vec(),
+            cipher_buffer,
+            tag,
+        }
+    }
+
+    #[test]
+    fn test_challenge63_tag_polynomial() {
+        let key = generate_random_bytes(Some(AES_BLOCK_SIZE));
+        let iv = generate_random_bytes(Some(GCM_IV_SIZE));
+
+        let message = encrypt(b"Rollin' in my 5.0", &key, &iv, b"header");
+        let h = Aes128Gcm::hash_key(&key);
+
+        // The mask is E(K, J0), with J0 = IV || 0^31 || 1
+        let mut j0 = iv.clone();
+        j0.extend_from_slice(&[0, 0, 0, 1]);
+        let s = Gf128::from_block(&Aes128Ecb::encrypt(&j0, &key, None)[..AES_BLOCK_SIZE]);
+
+        assert_eq!(message.tag_polynomial().evaluate(h), s);
+    }
+
+    #[test]
+    fn test_challenge63() {
+        let key = generate_random_bytes(Some(AES_BLOCK_SIZE));
+        let iv = generate_random_bytes(Some(GCM_IV_SIZE));
+
+        // Two messages leak under the same nonce
+        let first = encrypt(b"With my rag-top down so my hair can blow", &key, &iv, b"user=alice");
+        let second = encrypt(b"The girlies on standby waving just to say hi", &key, &iv, b"user=bob");
+
+        let candidates = recover_hash_key_candidates(&first, &second);
+        assert!(candidates.contains(&Aes128Gcm::hash_key(&key)));
+
+        // Flip the plain text of the first message and forge a tag for it with every candidate
+        let bit_flips = b"With".xor_all(b"Wout");
+        let mut forged_cipher = first.cipher_buffer.clone();
+        forged_cipher.iter_mut().zip(bit_flips).for_each(|(c, x)| *c ^= x);
+
+        let forged = candidates.into_iter().find_map(|h| {
+            let tag = forge_tag(h, &first, b"user=admin", &forged_cipher);
+            Aes128Gcm::decrypt(&forged_cipher, &key, &iv, b"user=admin", &tag).ok()
+        });
+
+        assert_eq!(forged.unwrap(), b"Wout my rag-top down so my hair can blow");
+    }
+}
@@ -0,0 +1,269 @@
+//! Key-Recovery Attacks on GCM with a Truncated MAC
+//! <https://cryptopals.com/sets/8/challenges/64>
+//!
+//! Squaring is linear over GF(2), so changing only the blocks multiplied by H^(2^i) changes the tag by
+//! E = sum D_i H^(2^i) = A_d H, where A_d = sum M(D_i) S^i is a 128 x 128 matrix over GF(2) which is linear
+//! in the bits d of the differences D_i. Forcing the first rows of A_d to zero makes forgeries much more
+//! likely, and every successful forgery leaks the remaining rows as linear equations on H.
+
+use crate::aes::{Aes128Gcm, AES_BLOCK_SIZE, GCM_IV_SIZE};
+use crate::gf128::Gf128;
+use crate::gf2::{Gf2Matrix, Gf2Vector};
+use crate::utils::generate_random_bytes;
+
+/// Tag bits left free once enough rows can be zeroed. Every forgery then costs about 2^8 queries but
+/// teaches us up to 8 equations, instead of a single one when zeroing all but one tag bit.
+const LEARNED_TAG_BITS: usize = 8;
+
+/// A message as seen on the wire, with a truncated tag
+#[derive(Debug, Clone)]
+pub struct TruncatedGcmMessage {
+    pub iv: Vec<u8>,
+    pub cipher_buffer: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+/// Encrypts with tags truncated to `tag_len` bytes, and tells whether a message authenticates
+struct TruncatedGcmOracle64 {
+    key: Vec<u8>,
+    tag_len: usize,
+}
+
+impl TruncatedGcmOracle64 {
+    pub fn new(tag_len: usize) -> Self {
+        Self {
+            key: generate_random_bytes(Some(AES_BLOCK_SIZE)),
+            tag_len,
+        }
+    }
+
+    pub fn encrypt(&self, plain_buffer: &[u8]) -> TruncatedGcmMessage {
+        let iv = generate_random_bytes(Some(GCM_IV_SIZE));
+        let (cipher_buffer, tag) = Aes128Gcm::encrypt(plain_buffer, &self.key, &iv, &[]);
+
+        TruncatedGcmMessage {
+            iv,
+            cipher_buffer,
+            tag: tag[..self.tag_len].to_vec(),
+        }
+    }
+
+    pub fn accepts(&self, message: &TruncatedGcmMessage) -> bool {
+        Aes128Gcm::decrypt(
+            &message.cipher_buffer,
+            &self.key,
+            &message.iv,
+            &[],
+            &message.tag,
+        )
+        .is_ok()
+    }
+
+    /// Only for verifying the attack
+    fn hash_key(&self) -> Gf128 {
+        Aes128Gcm::hash_key(&self.key)
+    }
+}
+
+struct FergusonAttack<'a> {
+    oracle: &'a TruncatedGcmOracle64,
+    message: TruncatedGcmMessage,
+    /// The message has 2^n cipher blocks, giving n differences D_1, ..., D_n
+    block_exponent: usize,
+    /// S^1, ..., S^n
+    squarings: Vec<Gf2Matrix>,
+    /// M(x^b) for every bit b of a difference
+    bit_multiplications: Vec<Gf2Matrix>,
+}
+
+impl<'a> FergusonAttack<'a> {
+    pub fn new(oracle: &'a TruncatedGcmOracle64, message: TruncatedGcmMessage) -> Self {
+        let block_count = message.cipher_buffer.len() / AES_BLOCK_SIZE;
+        assert!(
+            block_count.is_power_of_two() && message.cipher_buffer.len() % AES_BLOCK_SIZE == 0,
+            "The message needs 2^n full blocks"
+        );
+        let block_exponent = block_count.trailing_zeros() as usize;
+
+        let squaring = Gf128::squaring_matrix();
+        let squarings =
+            std::iter::successors(Some(squaring.clone()), |power| Some(power.mul(&squaring)))
+                .take(block_exponent)
+                .collect();
+
+        let bit_multiplications = (0..128)
+            .map(|bit| Gf128(1 << bit).multiplication_matrix())
+            .collect();
+
+        Self {
+            oracle,
+            message,
+            block_exponent,
+            squarings,
+            bit_multiplications,
+        }
+    }
+
+    fn tag_bits(&self) -> usize {
+        self.message.tag.len() * 8
+    }
+
+    /// Splits d into the differences D_1, ..., D_n
+    fn differences(&self, d: &Gf2Vector) -> Vec<Gf128> {
+        d.ones().fold(
+            vec![Gf128::ZERO; self.block_exponent],
+            |mut differences, bit| {
+                differences[bit / 128].0 |= 1 << (bit % 128);
+                differences
+            },
+        )
+    }
+
+    /// A_d = sum M(D_i) S^i
+    fn error_matrix(&self, d: &Gf2Vector) -> Gf2Matrix {
+        self.differences(d)
+            .into_iter()
+            .zip(self.squarings.iter())
+            .fold(Gf2Matrix::zero(128, 128), |acc, (difference, squaring)| {
+                acc.add(&difference.multiplication_matrix().mul(squaring))
+            })
+    }
+
+    /// Matrix T mapping the bits of d to the first `zero_rows` rows of A_d X, flattened row by row.
+    /// Its kernel gives the differences leaving those tag bits unchanged for every H = X h'.
+    fn dependency_matrix(&self, x: &Gf2Matrix, zero_rows: usize) -> Gf2Matrix {
+        let columns = self
+            .squarings
+            .iter()
+            .flat_map(|squaring| {
+                let projected = squaring.mul(x);
+
+                self.bit_multiplications.iter().map(move |multiplication| {
+                    let top =
+                        Gf2Matrix::from_rows(128, multiplication.rows()[..zero_rows].to_vec());
+
+                    top.mul(&projected)
+                        .rows()
+                        .iter()
+                        .fold(Gf2Vector::zero(0), |acc, row| acc.concat(row))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Gf2Matrix::from_columns(zero_rows * x.num_cols(), &columns)
+    }
+
+    /// Adds D_i to the block multiplied by H^(2^i). With L cipher blocks followed by the length block, the
+    /// j-th block (from 0) is multiplied by H^(L + 1 - j).
+    fn forge(&self, d: &Gf2Vector) -> TruncatedGcmMessage {
+        let mut forged = self.message.clone();
+        let block_count = 1 << self.block_exponent;
+
+        for (i, difference) in self.differences(d).into_iter().enumerate() {
+            let j = block_count + 1 - (1 << (i + 1));
+            let block = &mut forged.cipher_buffer[j * AES_BLOCK_SIZE..(j + 1) * AES_BLOCK_SIZE];
+            for (byte, difference_byte) in block.iter_mut().zip(difference.to_block()) {
+                *byte ^= difference_byte;
+            }
+        }
+
+        forged
+    }
+
+    /// Queries random differences from the kernel of T until one forges a valid tag
+    fn find_forgery(&self, kernel: &[Gf2Vector]) -> Gf2Vector {
+        loop {
+            let selection = Gf2Vector::random(kernel.len());
+            let d =
+                selection
+                    .ones()
+                    .fold(Gf2Vector::zero(self.block_exponent * 128), |mut acc, i| {
+                        acc.xor_assign(&kernel[i]);
+                        acc
+                    });
+
+            if !d.is_zero() && self.oracle.accepts(&self.forge(&d)) {
+                return d;
+            }
+        }
+    }
+
+    pub fn recover_hash_key(&self) -> Option<Gf128> {
+        let tag_bits = self.tag_bits();
+        let unknowns = self.block_exponent * 128;
+
+        // Linear equations K H = 0 learnt so far, H lies in the span of the columns of X
+        let mut equations = Gf2Matrix::zero(0, 128);
+
+        loop {
+            let basis = equations.kernel();
+            match basis.len() {
+                0 => return None,
+                1 => return Some(Gf128::from_vector(&basis[0])),
+                _ => {}
+            }
+            let x = Gf2Matrix::from_columns(128, &basis);
+
+            // T must keep a non-trivial kernel, and some tag bits are left free to learn from
+            let zero_rows =
+                ((unknowns - 1) / x.num_cols()).min(tag_bits.saturating_sub(LEARNED_TAG_BITS));
+            let kernel = self.dependency_matrix(&x, zero_rows).kernel();
+            if kernel.is_empty() {
+                return None;
+            }
+
+            let d = self.find_forgery(&kernel);
+            let error_matrix = self.error_matrix(&d);
+            for row in error_matrix.rows()[zero_rows..tag_bits].iter() {
+                equations.push_row(row.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_ferguson_attack(tag_len: usize, block_exponent: usize) {
+        let oracle = TruncatedGcmOracle64::new(tag_len);
+        let message = oracle.encrypt(&vec![0; AES_BLOCK_SIZE << block_exponent]);
+
+        let attack = FergusonAttack::new(&oracle, message);
+        let h = attack.recover_hash_key().unwrap();
+
+        assert_eq!(h, oracle.hash_key());
+    }
+
+    #[test]
+    fn test_challenge64_error_matrix() {
+        let oracle = TruncatedGcmOracle64::new(4);
+        let message = oracle.encrypt(&[0; AES_BLOCK_SIZE << 4]);
+        let attack = FergusonAttack::new(&oracle, message.clone());
+
+        // The tag changes by exactly A_d H
+        let h = oracle.hash_key();
+        let d = Gf2Vector::random(4 * 128);
+        let forged = attack.forge(&d);
+        let difference = Aes128Gcm::ghash(h, &[], &forged.cipher_buffer)
+            + Aes128Gcm::ghash(h, &[], &message.cipher_buffer);
+
+        assert_eq!(
+            Gf128::from_vector(&attack.error_matrix(&d).mul_vector(&h.to_vector())),
+            difference
+        );
+    }
+
+    #[test]
+    fn test_challenge64_reduced() {
+        // 16 bit tags and 2^9 blocks
+        test_ferguson_attack(2, 9);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_challenge64() {
+        // 32 bit tags and 2^17 blocks
+        test_ferguson_attack(4, 17);
+    }
+}
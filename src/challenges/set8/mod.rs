@@ -0,0 +1,8 @@
+pub mod challenge57;
+mod challenge58;
+mod challenge59;
+mod challenge60;
+mod challenge61;
+mod challenge62;
+mod challenge63;
+mod challenge64;
@@ -0,0 +1,95 @@
+//! Affine cipher, enciphering the letter of index x as a * x + b modulo 26
+
+use anyhow::Result;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+use super::{best_key, map_letters, ClassicalCipher, Solution, ALPHABET_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Affine {
+    a: u8,
+    b: u8,
+    /// Inverse of `a` modulo 26
+    a_inverse: u8,
+}
+
+impl Affine {
+    /// None when `a` has no inverse modulo 26, as such keys cannot be deciphered
+    pub fn new(a: u8, b: u8) -> Option<Self> {
+        let a = a % ALPHABET_SIZE;
+        let a_inverse =
+            (1..ALPHABET_SIZE).find(|&x| a as u16 * x as u16 % ALPHABET_SIZE as u16 == 1)?;
+
+        Some(Self {
+            a,
+            b: b % ALPHABET_SIZE,
+            a_inverse,
+        })
+    }
+
+    pub fn a(&self) -> u8 {
+        self.a
+    }
+
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+
+    /// The 312 valid keys
+    pub fn keys() -> impl Iterator<Item = Self> {
+        (1..ALPHABET_SIZE)
+            .flat_map(|a| (0..ALPHABET_SIZE).map(move |b| (a, b)))
+            .filter_map(|(a, b)| Self::new(a, b))
+    }
+}
+
+impl ClassicalCipher for Affine {
+    fn encrypt(&self, plaintext: &str) -> String {
+        map_letters(plaintext, |x| {
+            ((self.a as u16 * x as u16 + self.b as u16) % ALPHABET_SIZE as u16) as u8
+        })
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        map_letters(ciphertext, |y| {
+            let shifted = (y + ALPHABET_SIZE - self.b) as u16;
+            (self.a_inverse as u16 * shifted % ALPHABET_SIZE as u16) as u8
+        })
+    }
+}
+
+/// Tries every key, the key space being small enough
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<Affine>> {
+    best_key(scorer, ciphertext, Affine::keys())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::CombinedScorer;
+
+    #[test]
+    fn test_affine() {
+        let cipher = Affine::new(5, 8).unwrap();
+
+        assert_eq!(cipher.encrypt("Affine cipher"), "Ihhwvc swfrcp");
+        assert_eq!(cipher.decrypt("Ihhwvc swfrcp"), "Affine cipher");
+        assert_eq!(Affine::new(13, 1), None);
+        assert_eq!(Affine::keys().count(), 312);
+    }
+
+    #[test]
+    fn test_break_affine() {
+        let plaintext = "From the top of the tower he could see the whole bay.";
+        let ciphertext = Affine::new(7, 3).unwrap().encrypt(plaintext);
+
+        let solution = break_cipher(&CombinedScorer::new(), &ciphertext).unwrap();
+
+        assert_eq!((solution.key.a(), solution.key.b()), (7, 3));
+        assert_eq!(solution.plaintext, plaintext);
+    }
+}
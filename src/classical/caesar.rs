@@ -0,0 +1,74 @@
+//! Caesar cipher, shifting every letter by the same amount
+
+use anyhow::Result;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+use super::{best_key, map_letters, rank_keys, ClassicalCipher, Solution, ALPHABET_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caesar {
+    pub shift: u8,
+}
+
+impl Caesar {
+    pub fn new(shift: u8) -> Self {
+        Self {
+            shift: shift % ALPHABET_SIZE,
+        }
+    }
+}
+
+impl ClassicalCipher for Caesar {
+    fn encrypt(&self, plaintext: &str) -> String {
+        map_letters(plaintext, |i| i + self.shift)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        map_letters(ciphertext, |i| i + ALPHABET_SIZE - self.shift)
+    }
+}
+
+/// Every shift, best first
+pub fn rank_shifts<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Vec<Solution<Caesar>>> {
+    rank_keys(scorer, ciphertext, (0..ALPHABET_SIZE).map(Caesar::new))
+}
+
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<Caesar>> {
+    best_key(scorer, ciphertext, (0..ALPHABET_SIZE).map(Caesar::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::{ChiSquaredScorer, NgramScorer};
+
+    #[test]
+    fn test_caesar() {
+        let cipher = Caesar::new(3);
+
+        assert_eq!(cipher.encrypt("Veni, vidi, vici."), "Yhql, ylgl, ylfl.");
+        assert_eq!(cipher.decrypt("Yhql, ylgl, ylfl."), "Veni, vidi, vici.");
+        assert_eq!(Caesar::new(29), cipher);
+    }
+
+    #[test]
+    fn test_break_caesar() {
+        let plaintext = "Nobody in the village remembered who had built the tower.";
+        let ciphertext = Caesar::new(11).encrypt(plaintext);
+
+        for solution in [
+            break_cipher(&ChiSquaredScorer::new(), &ciphertext).unwrap(),
+            break_cipher(&NgramScorer::new(), &ciphertext).unwrap(),
+        ] {
+            assert_eq!(solution.key.shift, 11);
+            assert_eq!(solution.plaintext, plaintext);
+        }
+    }
+}
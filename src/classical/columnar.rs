@@ -0,0 +1,208 @@
+//! Columnar transposition: the text is written in rows under a keyword and read off column by
+//! column, in the alphabetical order of the keyword letters
+//!
+//! Every character is moved, spaces and punctuation included, and the last row may be short.
+//! Putting two columns side by side in the right order lines up the letters of words, so the
+//! breaker climbs over column orders as for substitution, for every width tried.
+
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::cmp::Reverse;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+use super::{climb, move_back, move_forward, swap, ClassicalCipher, Move, Solution};
+
+/// Widths tried when none are given
+pub const DEFAULT_WIDTHS: RangeInclusive<usize> = 2..=12;
+
+/// Climbs from the columns in order and then from random orders when none are given
+pub const DEFAULT_RESTARTS: usize = 4;
+
+/// Runs of columns already in the right order are moved as well as swapped, keeping them whole
+const MOVES: &[Move] = &[swap, move_forward, move_back];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Columnar {
+    /// Columns in the order they are read off
+    order: Vec<usize>,
+}
+
+impl Columnar {
+    /// None when the keyword is empty. Equal letters are read from left to right.
+    pub fn new(keyword: &str) -> Option<Self> {
+        let letters = keyword.to_lowercase().chars().collect::<Vec<_>>();
+        let mut order = (0..letters.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&column| letters[column]);
+
+        Self::from_order(order)
+    }
+
+    /// None unless `order` holds every column once
+    pub fn from_order(order: Vec<usize>) -> Option<Self> {
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+
+        (!order.is_empty() && sorted.iter().copied().eq(0..order.len())).then(|| Self { order })
+    }
+
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    pub fn width(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Number of characters in every column of a text of `length` characters
+    fn column_lengths(&self, length: usize) -> Vec<usize> {
+        let width = self.width();
+
+        (0..width)
+            .map(|column| length / width + (column < length % width) as usize)
+            .collect()
+    }
+}
+
+impl ClassicalCipher for Columnar {
+    fn encrypt(&self, plaintext: &str) -> String {
+        let chars = plaintext.chars().collect::<Vec<_>>();
+
+        self.order
+            .iter()
+            .flat_map(|&column| chars.iter().skip(column).step_by(self.width()))
+            .collect()
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        let mut chars = ciphertext.chars();
+        let lengths = self.column_lengths(ciphertext.chars().count());
+
+        let mut columns = vec![Vec::new(); self.width()];
+        for &column in &self.order {
+            columns[column] = chars.by_ref().take(lengths[column]).collect();
+        }
+
+        (0..lengths[0])
+            .flat_map(|row| columns.iter().filter_map(move |column| column.get(row)))
+            .collect()
+    }
+}
+
+/// Climbs over the column orders of a width, from the columns in order and then `restarts`
+/// times from random orders drawn from `rng`
+pub fn break_cipher_with_width<S: PatternScorer + ?Sized, R: Rng + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    width: usize,
+    restarts: usize,
+    rng: &mut R,
+) -> Result<Solution<Columnar>> {
+    // The climb rearranges the stretches of ciphertext put under every column, the inverse of
+    // the order they are read off, so that moving one keeps those it lines up with
+    let key = |stretches: &[u8]| {
+        let mut order = vec![0; stretches.len()];
+        for (column, &stretch) in stretches.iter().enumerate() {
+            order[stretch as usize] = column;
+        }
+        Columnar::from_order(order).unwrap()
+    };
+    let mut order = (0..width).map(|column| column as u8).collect::<Vec<_>>();
+    let mut best = climb(scorer, ciphertext, order.clone(), MOVES, key)?;
+
+    for _ in 0..restarts {
+        order.shuffle(rng);
+
+        let solution = climb(scorer, ciphertext, order.clone(), MOVES, key)?;
+        if solution.score > best.score {
+            best = solution;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Breaks every width in `widths` and keeps the plaintext scoring best, the narrowest among
+/// equals
+pub fn break_cipher_with_rng<S: PatternScorer + ?Sized, R: Rng + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    widths: RangeInclusive<usize>,
+    restarts: usize,
+    rng: &mut R,
+) -> Result<Solution<Columnar>> {
+    // Column orders are climbed over as bytes, and wider keys are of no practical use anyway
+    let mut solutions = widths
+        .filter(|&width| width > 0 && width <= u8::MAX as usize + 1)
+        .map(|width| break_cipher_with_width(scorer, ciphertext, width, restarts, rng))
+        .collect::<Result<Vec<_>>>()?;
+
+    // The sort is stable, keeping the narrowest first among equal scores
+    solutions.sort_by_key(|solution| Reverse(solution.score));
+    solutions
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No width to try"))
+}
+
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<Columnar>> {
+    break_cipher_with_rng(
+        scorer,
+        ciphertext,
+        DEFAULT_WIDTHS,
+        DEFAULT_RESTARTS,
+        &mut rand::thread_rng(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::NgramScorer;
+    use crate::classical::SAMPLE;
+    use crate::pcg::Pcg32;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_columnar() {
+        let cipher = Columnar::new("zebras").unwrap();
+
+        assert_eq!(cipher.order(), [4, 2, 1, 3, 5, 0]);
+        assert_eq!(
+            cipher.encrypt("WEAREDISCOVEREDFLEEATONCE"),
+            "EVLNACDTESEAROFODEECWIREE"
+        );
+        assert_eq!(
+            cipher.decrypt("EVLNACDTESEAROFODEECWIREE"),
+            "WEAREDISCOVEREDFLEEATONCE"
+        );
+        assert_eq!(Columnar::new(""), None);
+        assert_eq!(Columnar::from_order(vec![0, 2]), None);
+    }
+
+    #[test]
+    fn test_break_columnar() {
+        let plaintext = &SAMPLE[..300];
+        let ciphertext = Columnar::new("harbour").unwrap().encrypt(plaintext);
+        let mut rng = Pcg32::seed_from_u64(50);
+
+        let solution = break_cipher_with_rng(
+            &NgramScorer::new(),
+            &ciphertext,
+            2..=8,
+            DEFAULT_RESTARTS,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert_eq!(solution.key, Columnar::new("harbour").unwrap());
+        assert_eq!(solution.plaintext, plaintext);
+    }
+}
@@ -0,0 +1,184 @@
+//! Classical pen-and-paper ciphers over the Latin alphabet, with breakers ranking candidate keys
+//! by how plausible their plaintexts read
+//!
+//! The substitution ciphers only encipher ASCII letters, keeping their case, and the
+//! transpositions move every character, so word breaks and punctuation survive and the scorers
+//! trained on ordinary text apply to the candidate plaintexts as they are. Playfair, working on
+//! pairs of letters, is the exception.
+
+pub mod affine;
+pub mod caesar;
+pub mod columnar;
+pub mod playfair;
+pub mod rail_fence;
+pub mod substitution;
+pub mod vigenere;
+
+use anyhow::{anyhow, Result};
+
+use std::cmp::Reverse;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+pub const ALPHABET_SIZE: u8 = 26;
+
+pub trait ClassicalCipher {
+    fn encrypt(&self, plaintext: &str) -> String;
+    fn decrypt(&self, ciphertext: &str) -> String;
+}
+
+/// A candidate key with the plaintext it gives
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<K> {
+    pub key: K,
+    pub score: u16,
+    pub plaintext: String,
+}
+
+/// Index of an ASCII letter in the alphabet, whatever its case
+pub fn letter_index(c: char) -> Option<u8> {
+    c.is_ascii_alphabetic()
+        .then(|| c.to_ascii_lowercase() as u8 - b'a')
+}
+
+/// The letters of `text` as alphabet indices, everything else dropped
+pub fn letter_indices(text: &str) -> Vec<u8> {
+    text.chars().filter_map(letter_index).collect()
+}
+
+/// Maps the alphabet index of every ASCII letter of `text` through `f`, keeping its case and
+/// leaving other characters alone
+pub fn map_letters<F: FnMut(u8) -> u8>(text: &str, mut f: F) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a'..='z' => (b'a' + f(c as u8 - b'a') % ALPHABET_SIZE) as char,
+            'A'..='Z' => (b'A' + f(c as u8 - b'A') % ALPHABET_SIZE) as char,
+            _ => c,
+        })
+        .collect()
+}
+
+/// Decrypts `ciphertext` with every key, best scoring plaintexts first, ties going to the key
+/// tried first
+pub fn rank_keys<K, S, I>(scorer: &S, ciphertext: &str, keys: I) -> Result<Vec<Solution<K>>>
+where
+    K: ClassicalCipher,
+    S: PatternScorer + ?Sized,
+    I: IntoIterator<Item = K>,
+{
+    let mut solutions = keys
+        .into_iter()
+        .map(|key| {
+            let plaintext = key.decrypt(ciphertext);
+            let score = scorer.get_score(&plaintext)?;
+
+            Ok(Solution {
+                key,
+                score,
+                plaintext,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // The sort is stable, keeping the order of the keys among equal scores
+    solutions.sort_by_key(|solution| Reverse(solution.score));
+    Ok(solutions)
+}
+
+/// The key whose plaintext scores best, the first tried among equals
+pub fn best_key<K, S, I>(scorer: &S, ciphertext: &str, keys: I) -> Result<Solution<K>>
+where
+    K: ClassicalCipher,
+    S: PatternScorer + ?Sized,
+    I: IntoIterator<Item = K>,
+{
+    rank_keys(scorer, ciphertext, keys)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No key to try"))
+}
+
+/// A rearrangement of a permutation between two positions, the first before the second
+type Move = fn(&mut [u8], usize, usize);
+
+fn swap(permutation: &mut [u8], a: usize, b: usize) {
+    permutation.swap(a, b);
+}
+
+/// Moves the entry at `a` to `b`, shifting the ones in between back
+fn move_forward(permutation: &mut [u8], a: usize, b: usize) {
+    permutation[a..=b].rotate_left(1);
+}
+
+/// Moves the entry at `b` to `a`, shifting the ones in between on
+fn move_back(permutation: &mut [u8], a: usize, b: usize) {
+    permutation[a..=b].rotate_right(1);
+}
+
+/// Rearranges `permutation` by `moves` while the plaintext of the key `key` builds from it
+/// scores better, until no single move does
+fn climb<K, S, F>(
+    scorer: &S,
+    ciphertext: &str,
+    mut permutation: Vec<u8>,
+    moves: &[Move],
+    key: F,
+) -> Result<Solution<K>>
+where
+    K: ClassicalCipher,
+    S: PatternScorer + ?Sized,
+    F: Fn(&[u8]) -> K,
+{
+    let mut best_score = scorer.get_score(&key(&permutation).decrypt(ciphertext))?;
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for a in 0..permutation.len() {
+            for b in a + 1..permutation.len() {
+                for rearrange in moves {
+                    let mut candidate = permutation.clone();
+                    rearrange(&mut candidate, a, b);
+                    let score = scorer.get_score(&key(&candidate).decrypt(ciphertext))?;
+
+                    if score > best_score {
+                        permutation = candidate;
+                        best_score = score;
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let key = key(&permutation);
+    Ok(Solution {
+        plaintext: key.decrypt(ciphertext),
+        key,
+        score: best_score,
+    })
+}
+
+/// Text absent from the corpus the scorers are trained on
+#[cfg(test)]
+const SAMPLE: &str = "The lighthouse keeper climbed the narrow stairs every evening before \
+    the sun went down. He carried a small can of oil, a clean cloth and a book he never found \
+    the time to read. From the top of the tower he could see the whole bay, the fishing boats \
+    coming home and the long line of the cliffs to the north. When the wind was strong the \
+    glass rattled in its frame and the old man would sing to himself to keep his spirits up. \
+    Nobody in the village remembered who had built the tower, but everyone agreed that without \
+    its light many a sailor would have been lost on the rocks below. In winter the storms came \
+    one after another and the keeper hardly slept at all, watching the lamp through the long \
+    nights and writing down the ships that passed.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_letters() {
+        assert_eq!(map_letters("Hello, World!", |i| i + 1), "Ifmmp, Xpsme!");
+        assert_eq!(map_letters("xyz", |i| i + 3), "abc");
+        assert_eq!(letter_indices("a-Z b"), [0, 25, 1]);
+    }
+}
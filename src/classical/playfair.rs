@@ -0,0 +1,352 @@
+//! Playfair cipher, enciphering pairs of letters through a 5x5 square keyed by a keyword
+//!
+//! The square has no room for J, enciphered as I. The plaintext is split into pairs with an X
+//! between doubled letters and after a lone last letter, and everything but letters is dropped.
+//! Ciphertexts are written in capitals and plaintexts in small letters, the scorers being
+//! trained on ordinary text.
+//!
+//! Swapping pairs of letters of the square gets stuck quickly, so the breaker anneals: worse
+//! squares are accepted with a probability falling with the temperature, and rows and columns
+//! are swapped as well as letters.
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+use super::{letter_index, ClassicalCipher, Solution, ALPHABET_SIZE};
+
+const SIZE: usize = 5;
+const J: u8 = b'j' - b'a';
+const I: u8 = b'i' - b'a';
+/// Put between doubled letters and after a lone last letter
+const FILLER: u8 = b'x' - b'a';
+/// Put between two Xs
+const SECOND_FILLER: u8 = b'q' - b'a';
+
+/// Temperature, in points of score, the breaker starts from when none is given
+pub const DEFAULT_TEMPERATURE: f64 = 600.0;
+/// Squares tried by every run of the breaker when no number is given
+pub const DEFAULT_ITERATIONS: usize = 200_000;
+/// Runs of the breaker after the first when no number is given, one in four or so failing
+pub const DEFAULT_RESTARTS: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playfair {
+    /// Letters of the square row by row
+    square: [u8; SIZE * SIZE],
+    /// Position in the square of every letter, J sharing that of I
+    positions: [usize; 26],
+}
+
+impl Playfair {
+    /// The square of the letters of the keyword in order, without repeats, followed by the rest
+    /// of the alphabet
+    pub fn new(keyword: &str) -> Self {
+        let mut letters = keyword
+            .chars()
+            .filter_map(letter_index)
+            .chain(0..ALPHABET_SIZE)
+            .map(|letter| if letter == J { I } else { letter })
+            .collect::<Vec<_>>();
+        let mut seen = [false; 26];
+        letters.retain(|&letter| !std::mem::replace(&mut seen[letter as usize], true));
+
+        Self::from_letters(&letters).unwrap()
+    }
+
+    /// The square from its 25 letters row by row, None unless every letter but J is there once
+    pub fn from_square(square: &str) -> Option<Self> {
+        let letters = square
+            .chars()
+            .map(letter_index)
+            .collect::<Option<Vec<_>>>()?;
+
+        Self::from_letters(&letters)
+    }
+
+    fn from_letters(letters: &[u8]) -> Option<Self> {
+        if letters.len() != SIZE * SIZE || letters.contains(&J) {
+            return None;
+        }
+
+        let mut positions = [usize::MAX; 26];
+        for (position, &letter) in letters.iter().enumerate() {
+            positions[letter as usize] = position;
+        }
+        positions[J as usize] = positions[I as usize];
+        if positions.contains(&usize::MAX) {
+            return None;
+        }
+
+        let mut square = [0; SIZE * SIZE];
+        square.copy_from_slice(letters);
+        Some(Self { square, positions })
+    }
+
+    /// The letters of the square row by row, in capitals
+    pub fn square(&self) -> String {
+        self.square.iter().map(|i| (b'A' + i) as char).collect()
+    }
+
+    /// Enciphers a pair with a shift of 1 along rows and columns, deciphers it with a shift of 4
+    fn map_pair(&self, a: u8, b: u8, shift: usize) -> [u8; 2] {
+        let (row_a, column_a) = (
+            self.positions[a as usize] / SIZE,
+            self.positions[a as usize] % SIZE,
+        );
+        let (row_b, column_b) = (
+            self.positions[b as usize] / SIZE,
+            self.positions[b as usize] % SIZE,
+        );
+        let at = |row: usize, column: usize| self.square[row * SIZE + column];
+
+        if row_a == row_b {
+            [
+                at(row_a, (column_a + shift) % SIZE),
+                at(row_b, (column_b + shift) % SIZE),
+            ]
+        } else if column_a == column_b {
+            [
+                at((row_a + shift) % SIZE, column_a),
+                at((row_b + shift) % SIZE, column_b),
+            ]
+        } else {
+            [at(row_a, column_b), at(row_b, column_a)]
+        }
+    }
+}
+
+/// The letters of `plaintext` in pairs, with fillers between doubled letters and at the end
+fn digraphs(plaintext: &str) -> Vec<[u8; 2]> {
+    let mut letters = plaintext
+        .chars()
+        .filter_map(letter_index)
+        .map(|letter| if letter == J { I } else { letter })
+        .peekable();
+    let filler = |letter| {
+        if letter == FILLER {
+            SECOND_FILLER
+        } else {
+            FILLER
+        }
+    };
+
+    let mut digraphs = Vec::new();
+    while let Some(a) = letters.next() {
+        let b = match letters.peek() {
+            Some(&b) if b != a => letters.next().unwrap(),
+            _ => filler(a),
+        };
+        digraphs.push([a, b]);
+    }
+    digraphs
+}
+
+impl ClassicalCipher for Playfair {
+    fn encrypt(&self, plaintext: &str) -> String {
+        digraphs(plaintext)
+            .iter()
+            .flat_map(|&[a, b]| self.map_pair(a, b, 1))
+            .map(|letter| (b'A' + letter) as char)
+            .collect()
+    }
+
+    /// Fillers are left in, and a lone last letter is kept as it is
+    fn decrypt(&self, ciphertext: &str) -> String {
+        let letters = ciphertext
+            .chars()
+            .filter_map(letter_index)
+            .collect::<Vec<_>>();
+
+        letters
+            .chunks(2)
+            .flat_map(|pair| match *pair {
+                [a, b] => self.map_pair(a, b, SIZE - 1).to_vec(),
+                _ => pair.to_vec(),
+            })
+            .map(|letter| (b'a' + letter) as char)
+            .collect()
+    }
+}
+
+/// A random change of the square: mostly a swap of two letters, sometimes of two rows or of two
+/// columns, which keep most pairs enciphered the same
+fn mutate<R: Rng + ?Sized>(square: &mut [u8; SIZE * SIZE], rng: &mut R) {
+    let (a, b) = (rng.gen_range(0..SIZE), rng.gen_range(0..SIZE));
+
+    match rng.gen_range(0..10) {
+        0 => {
+            for column in 0..SIZE {
+                square.swap(a * SIZE + column, b * SIZE + column);
+            }
+        }
+        1 => {
+            for row in 0..SIZE {
+                square.swap(row * SIZE + a, row * SIZE + b);
+            }
+        }
+        _ => square.swap(rng.gen_range(0..SIZE * SIZE), rng.gen_range(0..SIZE * SIZE)),
+    }
+}
+
+/// Anneals from a random square
+fn anneal<S: PatternScorer + ?Sized, R: Rng + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    temperature: f64,
+    iterations: usize,
+    rng: &mut R,
+) -> Result<Solution<Playfair>> {
+    let mut letters = (0..ALPHABET_SIZE)
+        .filter(|&letter| letter != J)
+        .collect::<Vec<_>>();
+    letters.shuffle(rng);
+
+    let key = Playfair::from_letters(&letters).unwrap();
+    anneal_from(scorer, ciphertext, key, temperature, iterations, rng)
+}
+
+/// Anneals from the square of `key` over `iterations` changes, the temperature falling linearly
+/// from `temperature` to 0, and keeps the best square met
+fn anneal_from<S: PatternScorer + ?Sized, R: Rng + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    mut key: Playfair,
+    temperature: f64,
+    iterations: usize,
+    rng: &mut R,
+) -> Result<Solution<Playfair>> {
+    let mut score = scorer.get_score(&key.decrypt(ciphertext))?;
+    let mut best = (key.clone(), score);
+
+    for iteration in 0..iterations {
+        let current = temperature * (1.0 - iteration as f64 / iterations as f64);
+        let mut square = key.square;
+        mutate(&mut square, rng);
+        let candidate = Playfair::from_letters(&square).unwrap();
+        let candidate_score = scorer.get_score(&candidate.decrypt(ciphertext))?;
+
+        let delta = candidate_score as f64 - score as f64;
+        if delta >= 0.0 || rng.gen::<f64>() < (delta / current).exp() {
+            key = candidate;
+            score = candidate_score;
+            if score > best.1 {
+                best = (key.clone(), score);
+            }
+        }
+    }
+
+    let (key, score) = best;
+    Ok(Solution {
+        plaintext: key.decrypt(ciphertext),
+        key,
+        score,
+    })
+}
+
+/// Anneals `restarts` more times after a first run, from squares drawn from `rng`, and keeps
+/// the best plaintext. The plaintexts having no word breaks, the scorer should weigh the n-grams
+/// of text without them, as `NgramScorer::quadgrams(&LanguageModel::english_letters()?)`.
+pub fn break_cipher_with_rng<S: PatternScorer + ?Sized, R: Rng + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    temperature: f64,
+    iterations: usize,
+    restarts: usize,
+    rng: &mut R,
+) -> Result<Solution<Playfair>> {
+    let mut best = anneal(scorer, ciphertext, temperature, iterations, rng)?;
+
+    for _ in 0..restarts {
+        let solution = anneal(scorer, ciphertext, temperature, iterations, rng)?;
+        if solution.score > best.score {
+            best = solution;
+        }
+    }
+
+    Ok(best)
+}
+
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<Playfair>> {
+    break_cipher_with_rng(
+        scorer,
+        ciphertext,
+        DEFAULT_TEMPERATURE,
+        DEFAULT_ITERATIONS,
+        DEFAULT_RESTARTS,
+        &mut rand::thread_rng(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::NgramScorer;
+    use crate::classical::SAMPLE;
+    use crate::language_model::LanguageModel;
+    use crate::pcg::Pcg32;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_playfair() {
+        let cipher = Playfair::new("playfair example");
+
+        assert_eq!(cipher.square(), "PLAYFIREXMBCDGHKNOQSTUVWZ");
+        assert_eq!(
+            cipher.encrypt("Hide the gold in the tree stump"),
+            "BMODZBXDNABEKUDMUIXMMOUVIF"
+        );
+        assert_eq!(
+            cipher.decrypt("BMODZBXDNABEKUDMUIXMMOUVIF"),
+            "hidethegoldinthetrexestump"
+        );
+        assert_eq!(Playfair::from_square(&cipher.square()), Some(cipher));
+        assert_eq!(Playfair::from_square("ABCDEFGHIJKLMNOPQRSTUVWXY"), None);
+    }
+
+    #[test]
+    fn test_anneal_partly_known_square() {
+        let scorer = NgramScorer::quadgrams(&LanguageModel::english_letters().unwrap());
+        let key = Playfair::new("lighthouse");
+        let ciphertext = key.encrypt(SAMPLE);
+        let mut rng = Pcg32::seed_from_u64(50);
+
+        // Two pairs of letters out of place, as when most of the square has been worked out
+        let mut square = key.square;
+        square.swap(0, 7);
+        square.swap(12, 20);
+        let start = Playfair::from_letters(&square).unwrap();
+        assert_ne!(start.decrypt(&ciphertext), key.decrypt(&ciphertext));
+
+        let solution = anneal_from(&scorer, &ciphertext, start, 50.0, 3_000, &mut rng).unwrap();
+
+        assert_eq!(solution.plaintext, key.decrypt(&ciphertext));
+    }
+
+    #[ignore]
+    #[test]
+    fn test_break_playfair() {
+        let scorer = NgramScorer::quadgrams(&LanguageModel::english_letters().unwrap());
+        let ciphertext = Playfair::new("lighthouse").encrypt(SAMPLE);
+        let mut rng = Pcg32::seed_from_u64(50);
+
+        let solution = break_cipher_with_rng(
+            &scorer,
+            &ciphertext,
+            DEFAULT_TEMPERATURE,
+            DEFAULT_ITERATIONS,
+            DEFAULT_RESTARTS,
+            &mut rng,
+        )
+        .unwrap();
+
+        // Shifting the rows or the columns of the square round gives the same cipher
+        let expected = Playfair::new("lighthouse").decrypt(&ciphertext);
+        assert_eq!(solution.plaintext, expected);
+    }
+}
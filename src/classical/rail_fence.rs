@@ -0,0 +1,107 @@
+//! Rail fence: the text is written in a zigzag over a number of rails and read off rail by rail
+//!
+//! Every character is moved, spaces and punctuation included. There are hardly more keys than
+//! characters, so the breaker tries them all.
+
+use anyhow::Result;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+use super::{best_key, ClassicalCipher, Solution};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RailFence {
+    rails: usize,
+}
+
+impl RailFence {
+    /// None for less than two rails, which would leave the text as it is
+    pub fn new(rails: usize) -> Option<Self> {
+        (rails >= 2).then(|| Self { rails })
+    }
+
+    pub fn rails(&self) -> usize {
+        self.rails
+    }
+
+    /// The rail of every position of a text of `length` characters
+    fn zigzag(&self, length: usize) -> impl Iterator<Item = usize> {
+        let period = 2 * (self.rails - 1);
+
+        (0..length).map(move |i| (i % period).min(period - i % period))
+    }
+
+    /// The positions of a text of `length` characters in the order they are read off
+    fn positions(&self, length: usize) -> Vec<usize> {
+        let mut positions = (0..length).collect::<Vec<_>>();
+        let rails = self.zigzag(length).collect::<Vec<_>>();
+        positions.sort_by_key(|&i| rails[i]);
+
+        positions
+    }
+}
+
+impl ClassicalCipher for RailFence {
+    fn encrypt(&self, plaintext: &str) -> String {
+        let chars = plaintext.chars().collect::<Vec<_>>();
+
+        self.positions(chars.len())
+            .iter()
+            .map(|&i| chars[i])
+            .collect()
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        let chars = ciphertext.chars().collect::<Vec<_>>();
+        let mut plaintext = vec![' '; chars.len()];
+        for (&i, &c) in self.positions(chars.len()).iter().zip(&chars) {
+            plaintext[i] = c;
+        }
+
+        plaintext.into_iter().collect()
+    }
+}
+
+/// Tries every number of rails up to the length of the text, the fewest among equal scores
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<RailFence>> {
+    let length = ciphertext.chars().count();
+    let keys = (2..length.max(3)).filter_map(RailFence::new);
+
+    best_key(scorer, ciphertext, keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::NgramScorer;
+    use crate::classical::SAMPLE;
+
+    #[test]
+    fn test_rail_fence() {
+        let cipher = RailFence::new(3).unwrap();
+
+        assert_eq!(
+            cipher.encrypt("WEAREDISCOVEREDFLEEATONCE"),
+            "WECRLTEERDSOEEFEAOCAIVDEN"
+        );
+        assert_eq!(
+            cipher.decrypt("WECRLTEERDSOEEFEAOCAIVDEN"),
+            "WEAREDISCOVEREDFLEEATONCE"
+        );
+        assert_eq!(RailFence::new(1), None);
+    }
+
+    #[test]
+    fn test_break_rail_fence() {
+        let plaintext = &SAMPLE[..200];
+        let ciphertext = RailFence::new(5).unwrap().encrypt(plaintext);
+
+        let solution = break_cipher(&NgramScorer::new(), &ciphertext).unwrap();
+
+        assert_eq!(solution.key.rails(), 5);
+        assert_eq!(solution.plaintext, plaintext);
+    }
+}
@@ -0,0 +1,189 @@
+//! Monoalphabetic substitution, enciphering every letter as another through a scrambled alphabet
+//!
+//! Its 26! keys are out of reach of a search, so the breaker climbs: starting from the key
+//! matching the letter frequencies to those of English, it swaps pairs of letters as long as
+//! the plaintext scores better, restarting from random keys to escape local maxima.
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::cmp::Reverse;
+
+use crate::challenges::set1::challenge3::PatternScorer;
+
+use super::{letter_indices, map_letters, swap, ClassicalCipher, Solution, ALPHABET_SIZE};
+
+/// The letters from the most to the least frequent in English
+const ENGLISH_ORDER: &[u8; 26] = b"etaoinshrdlcumwfgypbvkjxqz";
+
+/// Climbs from the frequency key and then from random keys when none are given
+pub const DEFAULT_RESTARTS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    /// Cipher letter of every plaintext letter
+    encryption: [u8; 26],
+    /// Plaintext letter of every cipher letter
+    decryption: [u8; 26],
+}
+
+impl Substitution {
+    /// The key from the cipher alphabet, None unless `alphabet` holds every letter once
+    pub fn new(alphabet: &str) -> Option<Self> {
+        let indices = letter_indices(alphabet);
+        if indices.len() != alphabet.len() || indices.len() != ALPHABET_SIZE as usize {
+            return None;
+        }
+
+        let mut encryption = [0; 26];
+        encryption.copy_from_slice(&indices);
+
+        Self::from_encryption(encryption)
+    }
+
+    fn from_encryption(encryption: [u8; 26]) -> Option<Self> {
+        let mut decryption = [ALPHABET_SIZE; 26];
+        for (plain, &cipher) in encryption.iter().enumerate() {
+            decryption[cipher as usize] = plain as u8;
+        }
+
+        (!decryption.contains(&ALPHABET_SIZE)).then(|| Self {
+            encryption,
+            decryption,
+        })
+    }
+
+    fn from_decryption(decryption: [u8; 26]) -> Self {
+        let mut encryption = [0; 26];
+        for (cipher, &plain) in decryption.iter().enumerate() {
+            encryption[plain as usize] = cipher as u8;
+        }
+
+        Self {
+            encryption,
+            decryption,
+        }
+    }
+
+    /// The cipher alphabet in capitals
+    pub fn alphabet(&self) -> String {
+        self.encryption.iter().map(|i| (b'A' + i) as char).collect()
+    }
+}
+
+impl ClassicalCipher for Substitution {
+    fn encrypt(&self, plaintext: &str) -> String {
+        map_letters(plaintext, |i| self.encryption[i as usize])
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        map_letters(ciphertext, |i| self.decryption[i as usize])
+    }
+}
+
+/// The decryption key mapping the cipher letters, from the most to the least frequent, to the
+/// letters of English in the same order
+fn frequency_key(ciphertext: &str) -> [u8; 26] {
+    let mut counts = [0_usize; 26];
+    for i in letter_indices(ciphertext) {
+        counts[i as usize] += 1;
+    }
+
+    let mut by_frequency = (0..ALPHABET_SIZE).collect::<Vec<_>>();
+    by_frequency.sort_by_key(|&i| Reverse(counts[i as usize]));
+
+    let mut decryption = [0; 26];
+    for (&cipher, &plain) in by_frequency.iter().zip(ENGLISH_ORDER) {
+        decryption[cipher as usize] = plain - b'a';
+    }
+    decryption
+}
+
+/// Climbs from the decryption key `decryption`
+fn climb<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    decryption: [u8; 26],
+) -> Result<Solution<Substitution>> {
+    super::climb(
+        scorer,
+        ciphertext,
+        decryption.to_vec(),
+        &[swap],
+        |decryption| Substitution::from_decryption(decryption.try_into().unwrap()),
+    )
+}
+
+/// Climbs from the frequency key, then `restarts` times from random keys drawn from `rng`, and
+/// keeps the best plaintext. The scorer should weigh n-grams, the letter distribution being
+/// the same under every key with the same frequency matching.
+pub fn break_cipher_with_rng<S: PatternScorer + ?Sized, R: Rng + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    restarts: usize,
+    rng: &mut R,
+) -> Result<Solution<Substitution>> {
+    let mut best = climb(scorer, ciphertext, frequency_key(ciphertext))?;
+
+    for _ in 0..restarts {
+        let mut decryption = [0; 26];
+        for (i, letter) in decryption.iter_mut().enumerate() {
+            *letter = i as u8;
+        }
+        decryption.shuffle(rng);
+
+        let solution = climb(scorer, ciphertext, decryption)?;
+        if solution.score > best.score {
+            best = solution;
+        }
+    }
+
+    Ok(best)
+}
+
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<Substitution>> {
+    break_cipher_with_rng(
+        scorer,
+        ciphertext,
+        DEFAULT_RESTARTS,
+        &mut rand::thread_rng(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::NgramScorer;
+    use crate::classical::SAMPLE;
+    use crate::pcg::Pcg32;
+    use rand::SeedableRng;
+
+    const ALPHABET: &str = "QWERTYUIOPASDFGHJKLZXCVBNM";
+
+    #[test]
+    fn test_substitution() {
+        let cipher = Substitution::new(ALPHABET).unwrap();
+
+        assert_eq!(cipher.encrypt("Hello, World!"), "Itssg, Vgksr!");
+        assert_eq!(cipher.decrypt("Itssg, Vgksr!"), "Hello, World!");
+        assert_eq!(cipher.alphabet(), ALPHABET);
+        assert_eq!(Substitution::new("ABC"), None);
+        assert_eq!(Substitution::new("QQERTYUIOPASDFGHJKLZXCVBNM"), None);
+    }
+
+    #[test]
+    fn test_break_substitution() {
+        let ciphertext = Substitution::new(ALPHABET).unwrap().encrypt(SAMPLE);
+        let mut rng = Pcg32::seed_from_u64(49);
+
+        let solution =
+            break_cipher_with_rng(&NgramScorer::new(), &ciphertext, 2, &mut rng).unwrap();
+
+        // Letters missing from the text cannot be told apart, so only the plaintext is checked
+        assert_eq!(solution.plaintext, SAMPLE);
+    }
+}
@@ -0,0 +1,188 @@
+//! Vigenère cipher, shifting the letters in turn by those of a keyword
+//!
+//! The key only moves on over letters, as when enciphering by hand. Every column of letters
+//! enciphered with the same key letter is a Caesar cipher, and the key length shows in the
+//! statistics of the letters alone, as for repeating-key XOR.
+
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+
+use crate::challenges::set1::challenge3::PatternScorer;
+use crate::challenges::set1::challenge6::{rank_key_sizes, KeySizeCandidate};
+
+use super::caesar;
+use super::{letter_index, letter_indices, map_letters, ClassicalCipher, Solution, ALPHABET_SIZE};
+
+/// Key lengths tried when none are given
+pub const DEFAULT_KEY_LENGTHS: RangeInclusive<usize> = 1..=20;
+
+/// Most likely key lengths `break_cipher` tries
+const TRIED_KEY_LENGTHS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vigenere {
+    /// Shift of every key letter
+    shifts: Vec<u8>,
+}
+
+impl Vigenere {
+    /// None when the keyword has no letters
+    pub fn new(keyword: &str) -> Option<Self> {
+        Self::from_shifts(letter_indices(keyword))
+    }
+
+    pub fn from_shifts(shifts: Vec<u8>) -> Option<Self> {
+        (!shifts.is_empty()).then(|| Self {
+            shifts: shifts.iter().map(|shift| shift % ALPHABET_SIZE).collect(),
+        })
+    }
+
+    /// The keyword in capitals
+    pub fn keyword(&self) -> String {
+        self.shifts
+            .iter()
+            .map(|shift| (b'A' + shift) as char)
+            .collect()
+    }
+
+    /// Shifts the letters of `text` by the key letters, negated when deciphering
+    fn shift(&self, text: &str, decrypt: bool) -> String {
+        let mut shifts = self.shifts.iter().cycle();
+
+        map_letters(text, |i| {
+            let shift = shifts.next().unwrap();
+            if decrypt {
+                i + ALPHABET_SIZE - shift
+            } else {
+                i + shift
+            }
+        })
+    }
+}
+
+impl ClassicalCipher for Vigenere {
+    fn encrypt(&self, plaintext: &str) -> String {
+        self.shift(plaintext, false)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> String {
+        self.shift(ciphertext, true)
+    }
+}
+
+/// Statistics of the key lengths in `key_lengths` over the letters of `ciphertext`, most likely
+/// first
+pub fn rank_key_lengths(
+    ciphertext: &str,
+    key_lengths: RangeInclusive<usize>,
+) -> Vec<KeySizeCandidate> {
+    let letters = ciphertext
+        .chars()
+        .filter_map(letter_index)
+        .map(|i| b'a' + i)
+        .collect::<Vec<_>>();
+
+    rank_key_sizes(&letters, key_lengths)
+}
+
+/// Breaks every column of a key length as a Caesar cipher. The scorer only sees the letters of
+/// a column, which carry no word structure, so it should weigh their distribution.
+pub fn break_cipher_with_key_length<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    key_length: usize,
+) -> Result<Solution<Vigenere>> {
+    let letters = letter_indices(ciphertext);
+    let shifts = (0..key_length)
+        .map(|i| {
+            let column = letters
+                .iter()
+                .skip(i)
+                .step_by(key_length)
+                .map(|&letter| (b'a' + letter) as char)
+                .collect::<String>();
+            Ok(caesar::break_cipher(scorer, &column)?.key.shift)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let key =
+        Vigenere::from_shifts(shifts).ok_or_else(|| anyhow!("Key length must be positive"))?;
+    let plaintext = key.decrypt(ciphertext);
+
+    Ok(Solution {
+        score: scorer.get_score(&plaintext)?,
+        key,
+        plaintext,
+    })
+}
+
+/// Breaks the most likely key lengths in `key_lengths` and keeps the plaintext scoring best,
+/// the shortest key among equals, as multiples of the key length decipher just as well
+pub fn break_cipher_with_key_lengths<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+    key_lengths: RangeInclusive<usize>,
+) -> Result<Solution<Vigenere>> {
+    let mut solutions = rank_key_lengths(ciphertext, key_lengths)
+        .iter()
+        .take(TRIED_KEY_LENGTHS)
+        .map(|candidate| break_cipher_with_key_length(scorer, ciphertext, candidate.key_size))
+        .collect::<Result<Vec<_>>>()?;
+
+    solutions.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.key.shifts.len().cmp(&b.key.shifts.len()))
+    });
+    solutions
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Not enough letters for any key length"))
+}
+
+pub fn break_cipher<S: PatternScorer + ?Sized>(
+    scorer: &S,
+    ciphertext: &str,
+) -> Result<Solution<Vigenere>> {
+    break_cipher_with_key_lengths(scorer, ciphertext, DEFAULT_KEY_LENGTHS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::{ChiSquaredScorer, CombinedScorer};
+    use crate::classical::SAMPLE;
+
+    #[test]
+    fn test_vigenere() {
+        let cipher = Vigenere::new("Lemon").unwrap();
+
+        assert_eq!(cipher.encrypt("Attack at dawn!"), "Lxfopv ef rnhr!");
+        assert_eq!(cipher.decrypt("Lxfopv ef rnhr!"), "Attack at dawn!");
+        assert_eq!(cipher.keyword(), "LEMON");
+        assert_eq!(Vigenere::new("42"), None);
+    }
+
+    #[test]
+    fn test_rank_key_lengths() {
+        let ciphertext = Vigenere::new("harbour").unwrap().encrypt(SAMPLE);
+
+        let ranked = rank_key_lengths(&ciphertext, DEFAULT_KEY_LENGTHS);
+
+        assert_eq!(ranked[0].key_size, 7);
+    }
+
+    #[test]
+    fn test_break_vigenere() {
+        let ciphertext = Vigenere::new("harbour").unwrap().encrypt(SAMPLE);
+
+        for solution in [
+            break_cipher(&ChiSquaredScorer::new(), &ciphertext).unwrap(),
+            break_cipher(&CombinedScorer::new(), &ciphertext).unwrap(),
+        ] {
+            assert_eq!(solution.key.keyword(), "HARBOUR");
+            assert_eq!(solution.plaintext, SAMPLE);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Sources of the current time, so that attacks on time seeded generators can run without waiting
+
+use std::cell::Cell;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub trait Clock {
+    /// Seconds since the Unix epoch
+    fn now(&self) -> u64;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The wall clock, sleeping for real
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// A clock which only moves forward when sleeping, which returns immediately
+pub struct SimulatedClock {
+    elapsed: Cell<Duration>,
+}
+
+impl SimulatedClock {
+    /// Starts at `start` seconds since the Unix epoch
+    pub fn new(start: u64) -> Self {
+        Self {
+            elapsed: Cell::new(Duration::from_secs(start)),
+        }
+    }
+
+    /// Starts at the current system time
+    pub fn starting_now() -> Self {
+        Self::new(SystemClock.now())
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> u64 {
+        self.elapsed.get().as_secs()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_clock() {
+        let clock = SimulatedClock::new(1000);
+
+        clock.sleep(Duration::from_millis(1500));
+        assert_eq!(clock.now(), 1001);
+
+        clock.sleep(Duration::from_secs(40));
+        assert_eq!(clock.now(), 1041);
+    }
+}
@@ -0,0 +1,267 @@
+//! Crib dragging over ciphertexts sharing a keystream: a reused CTR nonce or one-time pad
+//!
+//! Guessing a word of one plaintext at some offset gives the keystream there, and with it the
+//! other plaintexts at the same offset. Sliding the word across every offset and scoring what
+//! the other plaintexts would read ranks the likely positions.
+
+use crate::challenges::set1::challenge3::PatternScorer;
+use crate::ctr_breaker::{apply_keystream, crib_keystream};
+use crate::plaintext::PlaintextClassifier;
+
+/// Bytes of context scored with every guessed byte, a quadgram
+const CONTEXT: usize = 3;
+
+/// A position of a crib with what it reveals
+#[derive(Debug, Clone, PartialEq)]
+pub struct CribMatch {
+    /// Ciphertext the crib was placed in
+    pub message: usize,
+    pub offset: usize,
+    /// Average score of the revealed texts
+    pub score: u16,
+    /// The other plaintexts under the crib, for the ones reaching that far
+    pub revealed: Vec<(usize, Vec<u8>)>,
+}
+
+pub struct CribDragger<C: PlaintextClassifier> {
+    ciphertexts: Vec<Vec<u8>>,
+    classifier: C,
+    keystream: Vec<Option<u8>>,
+}
+
+impl<C: PatternScorer> CribDragger<C> {
+    pub fn new(ciphertexts: &[Vec<u8>]) -> Self {
+        Self::with_classifier(ciphertexts, C::new())
+    }
+}
+
+impl<C: PlaintextClassifier> CribDragger<C> {
+    pub fn with_classifier(ciphertexts: &[Vec<u8>], classifier: C) -> Self {
+        let length = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+
+        Self {
+            ciphertexts: ciphertexts.to_vec(),
+            classifier,
+            keystream: vec![None; length],
+        }
+    }
+
+    /// What the other plaintexts read if `crib` is at `offset` of `message`, None when the crib
+    /// does not fit or nothing else reaches that far
+    fn reveal(&self, message: usize, offset: usize, crib: &[u8]) -> Option<Vec<(usize, Vec<u8>)>> {
+        let end = offset + crib.len();
+        let keystream = crib_keystream(&self.ciphertexts[message], offset, crib);
+        if keystream.len() < crib.len() {
+            return None;
+        }
+
+        let revealed = (0..self.ciphertexts.len())
+            .filter(|&other| other != message)
+            .filter_map(|other| {
+                let ciphertext = self.ciphertexts[other].get(offset..end)?;
+                Some((
+                    other,
+                    ciphertext
+                        .iter()
+                        .zip(&keystream)
+                        .map(|(c, k)| c ^ k)
+                        .collect(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        (!revealed.is_empty()).then(|| revealed)
+    }
+
+    /// Slides `crib` across every offset of every ciphertext, best placements first. Only the
+    /// placements where the classifier accepts all the revealed texts are kept.
+    pub fn drag(&self, crib: &[u8]) -> Vec<CribMatch> {
+        let mut matches = (0..self.ciphertexts.len())
+            .flat_map(|message| (0..self.ciphertexts[message].len()).map(move |o| (message, o)))
+            .filter_map(|(message, offset)| {
+                let revealed = self.reveal(message, offset, crib)?;
+                let scores = revealed
+                    .iter()
+                    .map(|(_, text)| self.classifier.classify(text).map(u32::from))
+                    .collect::<Option<Vec<_>>>()?;
+                let score = (scores.iter().sum::<u32>() / scores.len() as u32) as u16;
+
+                Some(CribMatch {
+                    message,
+                    offset,
+                    score,
+                    revealed,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.offset.cmp(&b.offset))
+                .then(a.message.cmp(&b.message))
+        });
+        matches
+    }
+
+    /// Fixes the keystream under `text` at `offset` of `message`, usually the best match of a crib,
+    /// as `FixedNonceCtrBreaker::apply_crib` does
+    pub fn place(&mut self, message: usize, offset: usize, text: &[u8]) {
+        let known = crib_keystream(&self.ciphertexts[message], offset, text);
+
+        for (position, byte) in (offset..).zip(known) {
+            self.keystream[position] = Some(byte);
+        }
+    }
+
+    pub fn keystream(&self) -> &[Option<u8>] {
+        &self.keystream
+    }
+
+    /// Sum of the scores of the plaintexts around `position` with the keystream byte `key`
+    /// there, None if the classifier rules any of them out. The guessed byte is scored with the
+    /// known bytes before it, or after it when it starts a known stretch.
+    fn score_guess(&self, position: usize, key: u8) -> Option<u32> {
+        let is_known = |i: &usize| self.keystream[*i].is_some();
+        let before = (0..position)
+            .rev()
+            .take(CONTEXT)
+            .take_while(is_known)
+            .count();
+        let after = (position + 1..self.keystream.len())
+            .take(CONTEXT)
+            .take_while(is_known)
+            .count();
+        let range = if before > 0 {
+            position - before..position + 1
+        } else {
+            position..position + 1 + after
+        };
+
+        self.ciphertexts
+            .iter()
+            .filter(|ciphertext| ciphertext.len() > position)
+            .map(|ciphertext| {
+                let text = range
+                    .clone()
+                    .filter_map(|i| {
+                        let k = if i == position {
+                            key
+                        } else {
+                            self.keystream[i]?
+                        };
+                        ciphertext.get(i).map(|c| c ^ k)
+                    })
+                    .collect::<Vec<_>>();
+
+                self.classifier.classify(&text).map(u32::from)
+            })
+            .sum()
+    }
+
+    /// The most plausible keystream byte at `position` with its score
+    fn best_guess(&self, position: usize) -> Option<(u32, u8)> {
+        (0..=255_u8)
+            .filter_map(|key| Some((self.score_guess(position, key)?, key)))
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+    }
+
+    /// Guesses unknown keystream bytes next to the known ones, extending the plaintexts of every
+    /// message on both sides of what is known, and returns the number of bytes guessed. Every
+    /// step takes the most plausible guess among the positions bordering the known keystream,
+    /// and `max_bytes` bounds how far a wrong guess can lead.
+    pub fn extend(&mut self, max_bytes: usize) -> usize {
+        let mut guessed = 0;
+
+        while guessed < max_bytes {
+            let length = self.keystream.len();
+            let best = (0..length)
+                .filter(|&i| {
+                    self.keystream[i].is_none()
+                        && ((i > 0 && self.keystream[i - 1].is_some())
+                            || (i + 1 < length && self.keystream[i + 1].is_some()))
+                })
+                .filter_map(|i| self.best_guess(i).map(|(score, key)| (score, i, key)))
+                .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+            match best {
+                Some((score, position, key)) if score > 0 => self.keystream[position] = Some(key),
+                _ => break,
+            }
+            guessed += 1;
+        }
+
+        guessed
+    }
+
+    pub fn plaintexts(&self) -> Vec<Vec<u8>> {
+        apply_keystream(&self.ciphertexts, &self.keystream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::NgramScorer;
+    use crate::ctr_breaker::FixedNonceCtrBreaker;
+    use crate::utils::generate_random_bytes;
+
+    const FIRST: &[u8] = b"It is a truth universally acknowledged, that a single man";
+    const SECOND: &[u8] = b"Call me Ishmael. Some years ago, never mind how long";
+
+    fn encrypt() -> Vec<Vec<u8>> {
+        let keystream = generate_random_bytes(Some(FIRST.len()));
+
+        [FIRST, SECOND]
+            .iter()
+            .map(|plaintext| {
+                plaintext
+                    .iter()
+                    .zip(&keystream)
+                    .map(|(p, k)| p ^ k)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crib_drag() {
+        let dragger = CribDragger::<NgramScorer>::new(&encrypt());
+
+        let matches = dragger.drag(b" years ");
+
+        let found = matches
+            .iter()
+            .take(3)
+            .find(|m| m.message == 1 && m.offset == 21)
+            .unwrap();
+        assert_eq!(found.revealed, [(0, b"ally ac".to_vec())]);
+    }
+
+    #[test]
+    fn test_crib_drag_into_ctr_breaker() {
+        let ciphertexts = encrypt();
+        let mut dragger = CribDragger::<NgramScorer>::new(&ciphertexts);
+        let mut breaker = FixedNonceCtrBreaker::<NgramScorer>::new(&ciphertexts);
+
+        let found = dragger.drag(b" years ")[0].clone();
+        dragger.place(found.message, found.offset, b" years ");
+        breaker.apply_crib(found.message, found.offset, b" years ");
+
+        assert_eq!(breaker.keystream(), dragger.keystream());
+    }
+
+    #[test]
+    fn test_crib_drag_extend() {
+        let mut dragger = CribDragger::<NgramScorer>::new(&encrypt());
+        dragger.place(0, 14, b"universally");
+        assert_eq!(&dragger.plaintexts()[1][14..25], b"l. Some yea");
+
+        let guessed = dragger.extend(8);
+        let plaintexts = dragger.plaintexts();
+
+        assert_eq!(guessed, 8);
+        assert_eq!(&plaintexts[0][14..33], b"universally acknowl");
+        assert_eq!(&plaintexts[1][14..33], b"l. Some years ago, ");
+    }
+}
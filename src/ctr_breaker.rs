@@ -0,0 +1,240 @@
+//! Breaks CTR ciphertexts encrypted under the same key and nonce, which all share a keystream
+//! <https://cryptopals.com/sets/3/challenges/20>
+
+use crate::challenges::set1::challenge3::{rank_single_byte_keys, PatternScorer};
+use crate::plaintext::PlaintextClassifier;
+
+/// Shown in place of the plaintext bytes whose keystream byte is unknown
+pub const UNKNOWN_BYTE: u8 = b'?';
+
+/// The ciphertexts decrypted as far as the keystream is known, unknown bytes being
+/// `UNKNOWN_BYTE`
+pub fn apply_keystream(ciphertexts: &[Vec<u8>], keystream: &[Option<u8>]) -> Vec<Vec<u8>> {
+    ciphertexts
+        .iter()
+        .map(|ciphertext| {
+            ciphertext
+                .iter()
+                .zip(keystream)
+                .map(|(c, k)| k.map_or(UNKNOWN_BYTE, |k| c ^ k))
+                .collect()
+        })
+        .collect()
+}
+
+/// The keystream from `offset` on under a known plaintext `crib` at `offset` of `ciphertext`,
+/// cut short where the ciphertext ends
+pub fn crib_keystream(ciphertext: &[u8], offset: usize, crib: &[u8]) -> Vec<u8> {
+    ciphertext
+        .iter()
+        .skip(offset)
+        .zip(crib)
+        .map(|(c, p)| c ^ p)
+        .collect()
+}
+
+/// What is known about the keystream byte at one position
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeystreamColumn {
+    pub byte: Option<u8>,
+    /// Number of ciphertexts long enough to reach the position
+    pub samples: usize,
+    /// Confidence margin of the statistical guess, 1 for bytes set by hand
+    pub confidence: f64,
+    /// Set by hand or from a crib, so kept by `recover_keystream`
+    pub fixed: bool,
+}
+
+pub struct FixedNonceCtrBreaker<C: PlaintextClassifier> {
+    ciphertexts: Vec<Vec<u8>>,
+    classifier: C,
+    columns: Vec<KeystreamColumn>,
+}
+
+impl<C: PatternScorer> FixedNonceCtrBreaker<C> {
+    pub fn new(ciphertexts: &[Vec<u8>]) -> Self {
+        Self::with_classifier(ciphertexts, C::new())
+    }
+}
+
+impl<C: PlaintextClassifier> FixedNonceCtrBreaker<C> {
+    pub fn with_classifier(ciphertexts: &[Vec<u8>], classifier: C) -> Self {
+        let length = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+        let columns = (0..length)
+            .map(|position| KeystreamColumn {
+                byte: None,
+                samples: ciphertexts.iter().filter(|c| c.len() > position).count(),
+                confidence: 0.0,
+                fixed: false,
+            })
+            .collect();
+
+        Self {
+            ciphertexts: ciphertexts.to_vec(),
+            classifier,
+            columns,
+        }
+    }
+
+    /// The bytes at `position` of every ciphertext reaching it, all encrypted with the same
+    /// keystream byte
+    pub fn column(&self, position: usize) -> Vec<u8> {
+        self.ciphertexts
+            .iter()
+            .filter_map(|ciphertext| ciphertext.get(position).copied())
+            .collect()
+    }
+
+    pub fn columns(&self) -> &[KeystreamColumn] {
+        &self.columns
+    }
+
+    /// Breaks every column as a single-byte XOR, so the tails of the longer ciphertexts are
+    /// recovered too, if less reliably. Columns fixed by hand are left alone.
+    pub fn recover_keystream(&mut self) -> Vec<Option<u8>> {
+        for position in 0..self.columns.len() {
+            if self.columns[position].fixed {
+                continue;
+            }
+
+            let ranking = rank_single_byte_keys(&self.classifier, &self.column(position), 1);
+            let column = &mut self.columns[position];
+            match ranking {
+                Ok(ranking) => {
+                    column.byte = Some(ranking.best().key);
+                    column.confidence = ranking.confidence;
+                }
+                Err(_) => {
+                    column.byte = None;
+                    column.confidence = 0.0;
+                }
+            }
+        }
+
+        self.keystream()
+    }
+
+    pub fn keystream(&self) -> Vec<Option<u8>> {
+        self.columns.iter().map(|column| column.byte).collect()
+    }
+
+    /// Corrects the keystream byte at `position` by hand
+    pub fn set_keystream_byte(&mut self, position: usize, byte: u8) {
+        let column = &mut self.columns[position];
+        column.byte = Some(byte);
+        column.confidence = 1.0;
+        column.fixed = true;
+    }
+
+    /// Fixes the keystream under a known plaintext `crib` at `offset` of one of the ciphertexts
+    pub fn apply_crib(&mut self, ciphertext: usize, offset: usize, crib: &[u8]) {
+        let known = crib_keystream(&self.ciphertexts[ciphertext], offset, crib);
+
+        for (position, byte) in (offset..).zip(known) {
+            self.set_keystream_byte(position, byte);
+        }
+    }
+
+    pub fn plaintexts(&self) -> Vec<Vec<u8>> {
+        apply_keystream(&self.ciphertexts, &self.keystream())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::{Aes128, AES_BLOCK_SIZE};
+    use crate::challenges::set1::challenge3::ChiSquaredScorer;
+    use crate::language_model::ENGLISH_CORPUS;
+    use crate::utils::generate_random_bytes;
+
+    /// Sentences of the corpus, of varying lengths
+    fn plaintexts() -> Vec<Vec<u8>> {
+        std::fs::read_to_string(ENGLISH_CORPUS)
+            .unwrap()
+            .split(['.', '\n'])
+            .map(str::trim)
+            .filter(|sentence| sentence.len() >= 20)
+            .map(|sentence| sentence.as_bytes().to_vec())
+            .collect()
+    }
+
+    fn encrypt(plaintexts: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let key = generate_random_bytes(Some(AES_BLOCK_SIZE));
+
+        plaintexts
+            .iter()
+            .map(|plaintext| plaintext.encrypt_aes_128_ctr(&key))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_keystream() {
+        let ciphertexts = vec![vec![1, 2, 3], vec![4]];
+
+        assert_eq!(
+            apply_keystream(&ciphertexts, &[Some(1), None, Some(3)]),
+            [vec![0, UNKNOWN_BYTE, 0], vec![5]]
+        );
+    }
+
+    #[test]
+    fn test_fixed_nonce_ctr_columns() {
+        let ciphertexts = vec![vec![1, 2, 3], vec![4], vec![5, 6]];
+        let breaker = FixedNonceCtrBreaker::<ChiSquaredScorer>::new(&ciphertexts);
+
+        assert_eq!(breaker.column(1), [2, 6]);
+        let samples = breaker
+            .columns()
+            .iter()
+            .map(|c| c.samples)
+            .collect::<Vec<_>>();
+        assert_eq!(samples, [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_fixed_nonce_ctr_recover_keystream() {
+        let plaintexts = plaintexts();
+        let ciphertexts = encrypt(&plaintexts);
+        let mut breaker = FixedNonceCtrBreaker::<ChiSquaredScorer>::new(&ciphertexts);
+
+        let keystream = breaker.recover_keystream();
+
+        // Compares against the keystream of the longest plaintext
+        let (longest, ciphertext) = plaintexts
+            .iter()
+            .zip(&ciphertexts)
+            .max_by_key(|(plaintext, _)| plaintext.len())
+            .unwrap();
+        let actual = longest.iter().zip(ciphertext).map(|(p, c)| p ^ c);
+        let well_sampled = breaker
+            .columns()
+            .iter()
+            .zip(keystream.iter().zip(actual))
+            .filter(|(column, _)| column.samples >= 20);
+        let (correct, total) = well_sampled.fold((0, 0), |(correct, total), (_, (k, a))| {
+            (correct + (*k == Some(a)) as usize, total + 1)
+        });
+
+        assert!(total > 50);
+        assert!(correct * 10 >= total * 9, "{} of {} bytes", correct, total);
+    }
+
+    #[test]
+    fn test_fixed_nonce_ctr_cribs() {
+        let plaintexts = plaintexts();
+        let ciphertexts = encrypt(&plaintexts);
+        let mut breaker = FixedNonceCtrBreaker::<ChiSquaredScorer>::new(&ciphertexts);
+        breaker.recover_keystream();
+
+        // The longest plaintext as a crib fixes every position, whatever the statistics guessed
+        let longest = (0..plaintexts.len())
+            .max_by_key(|&i| plaintexts[i].len())
+            .unwrap();
+        breaker.apply_crib(longest, 0, &plaintexts[longest]);
+        breaker.recover_keystream();
+
+        assert!(breaker.columns().iter().all(|column| column.fixed));
+        assert_eq!(breaker.plaintexts(), plaintexts);
+    }
+}
@@ -0,0 +1,126 @@
+//! Finite field Diffie-Hellman over a subgroup of prime order q of Z_p*
+//! https://en.wikipedia.org/wiki/Diffie%E2%80%93Hellman_key_exchange
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::dlog::Group;
+use crate::number_theory::random_in_range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhGroup {
+    pub p: BigUint,
+    pub g: BigUint,
+    pub q: BigUint,
+}
+
+impl DhGroup {
+    pub fn new(p: BigUint, g: BigUint, q: BigUint) -> Self {
+        Self { p, g, q }
+    }
+
+    fn parse(p: &str, g: &str, q: &str) -> Self {
+        let parse = |s: &str| BigUint::parse_bytes(s.as_bytes(), 10).unwrap();
+
+        Self::new(parse(p), parse(g), parse(q))
+    }
+
+    /// Group from challenge 57, where (p - 1) / q has many small factors
+    pub fn cryptopals_57() -> Self {
+        Self::parse(
+            "7199773997391911030609999317773941274322764333428698921736339643928346453700085358802973900\
+            485592910475480089726140708102474957429903531369589969318716771",
+            "4565356397095740655436854503483826832136106141639563487732438195343690437606117828318042418\
+            238184896212352329118608100083187535033402010599512641674644143",
+            "236234353446506858198510045061214171961",
+        )
+    }
+
+    /// Group from challenge 58, where the small factors of (p - 1) / q do not cover q
+    pub fn cryptopals_58() -> Self {
+        Self::parse(
+            "1147037487492527565811666350723216140208665025845389627453499167689899926264158151910107474\
+            0642369848233294239851519212341844337347119899874391456329785623",
+            "6229523353339612969781592660847410858898813587384599399782901799360636355667402585551677830\
+            09058567397963466103140082647486611657350811560630587013183357",
+            "335062023296420808191071248367701059461",
+        )
+    }
+
+    pub fn pow_g(&self, exponent: &BigUint) -> BigUint {
+        self.g.modpow(exponent, &self.p)
+    }
+}
+
+impl Group for DhGroup {
+    type Element = BigUint;
+
+    fn identity(&self) -> BigUint {
+        BigUint::one()
+    }
+
+    fn operate(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % &self.p
+    }
+
+    fn scalar(&self, a: &BigUint, k: &BigUint) -> BigUint {
+        a.modpow(k, &self.p)
+    }
+
+    fn element_hash(&self, a: &BigUint) -> u64 {
+        a.iter_u64_digits().next().unwrap_or(0)
+    }
+}
+
+pub struct DhKeyPair {
+    secret: BigUint,
+    pub public: BigUint,
+}
+
+impl DhKeyPair {
+    pub fn generate(group: &DhGroup) -> Self {
+        Self::from_secret(group, random_in_range(&BigUint::one(), &group.q))
+    }
+
+    pub fn from_secret(group: &DhGroup, secret: BigUint) -> Self {
+        let public = group.pow_g(&secret);
+
+        Self { secret, public }
+    }
+
+    /// Computes the shared secret without validating that the peer's key lies in the subgroup of order q
+    pub fn shared_secret(&self, group: &DhGroup, peer_public: &BigUint) -> BigUint {
+        peer_public.modpow(&self.secret, &group.p)
+    }
+
+    /// Only for verifying attacks
+    pub(crate) fn secret(&self) -> &BigUint {
+        &self.secret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dh_group_order() {
+        for group in [DhGroup::cryptopals_57(), DhGroup::cryptopals_58()] {
+            assert!(group.pow_g(&group.q).is_one());
+            assert_eq!((&group.p - 1_u32) % &group.q, BigUint::from(0_u32));
+        }
+    }
+
+    #[test]
+    fn test_dh() {
+        let group = DhGroup::cryptopals_57();
+
+        let alice = DhKeyPair::generate(&group);
+        let bob = DhKeyPair::generate(&group);
+
+        assert_eq!(
+            alice.shared_secret(&group, &bob.public),
+            bob.shared_secret(&group, &alice.public)
+        );
+    }
+}
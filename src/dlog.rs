@@ -0,0 +1,296 @@
+//! Discrete logarithm algorithms over generic cyclic groups
+//! https://en.wikipedia.org/wiki/Baby-step_giant-step
+//! https://en.wikipedia.org/wiki/Pollard%27s_kangaroo_algorithm
+//! https://en.wikipedia.org/wiki/Pohlig%E2%80%93Hellman_algorithm
+
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use num_traits::{One, Pow, ToPrimitive, Zero};
+
+use crate::number_theory::crt;
+
+/// A group written additively, so that `scalar(g, k)` is g^k in multiplicative notation
+pub trait Group {
+    type Element: Clone + PartialEq;
+
+    fn identity(&self) -> Self::Element;
+    fn operate(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+    fn scalar(&self, a: &Self::Element, k: &BigUint) -> Self::Element;
+
+    /// Deterministic mapping of an element to an integer, used to derive pseudo-random walks
+    fn element_hash(&self, a: &Self::Element) -> u64;
+}
+
+/// Baby-step giant-step.
+/// Finds x in [0, upper) such that `target` = x * `generator`, using O(sqrt(upper)) group operations and memory.
+pub fn baby_step_giant_step<G: Group>(
+    group: &G,
+    generator: &G::Element,
+    target: &G::Element,
+    upper: &BigUint,
+) -> Option<BigUint> {
+    let m = (upper.sqrt() + 1_u32).to_u64()?;
+
+    // Baby steps target + j * generator, bucketed by hash as elements are not required to be Hash
+    let mut baby_steps: HashMap<u64, Vec<(u64, G::Element)>> = HashMap::new();
+    let mut element = target.clone();
+    for j in 0..m {
+        baby_steps
+            .entry(group.element_hash(&element))
+            .or_default()
+            .push((j, element.clone()));
+        element = group.operate(&element, generator);
+    }
+
+    // Giant steps i * m * generator, a collision gives x = i * m - j
+    let giant_step = group.scalar(generator, &BigUint::from(m));
+    let mut current = group.identity();
+    for i in 0..=m {
+        let collisions = baby_steps
+            .get(&group.element_hash(&current))
+            .into_iter()
+            .flatten()
+            .filter(|(_, element)| *element == current);
+
+        for &(j, _) in collisions {
+            if let Some(x) = (i * m).checked_sub(j).map(BigUint::from) {
+                if x < *upper {
+                    return Some(x);
+                }
+            }
+        }
+
+        current = group.operate(&current, &giant_step);
+    }
+
+    None
+}
+
+/// Jump sizes of a kangaroo walk, selected by the hash of the current element
+#[derive(Debug, Clone)]
+pub struct JumpFunction {
+    sizes: Vec<BigUint>,
+    seed: u32,
+}
+
+impl JumpFunction {
+    pub fn new(sizes: Vec<BigUint>) -> Self {
+        assert!(!sizes.is_empty(), "A jump function needs at least one jump size");
+
+        Self { sizes, seed: 0 }
+    }
+
+    /// Jumps 2^0, ..., 2^(k - 1)
+    pub fn powers_of_two(k: u64) -> Self {
+        Self::new((0..k).map(|i| BigUint::one() << i).collect())
+    }
+
+    /// Powers of two with a mean close to sqrt(width) / 2
+    pub fn for_interval(width: &BigUint) -> Self {
+        Self::powers_of_two((width.bits() / 2 + 1).max(2))
+    }
+
+    /// Different seeds partition the group differently, giving independent walks
+    pub fn with_seed(self, seed: u32) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn sizes(&self) -> &[BigUint] {
+        &self.sizes
+    }
+
+    pub fn mean(&self) -> BigUint {
+        self.sizes.iter().sum::<BigUint>() / self.sizes.len()
+    }
+
+    pub fn index(&self, hash: u64) -> usize {
+        (hash.rotate_left(self.seed.wrapping_mul(13)) % self.sizes.len() as u64) as usize
+    }
+}
+
+/// Number of differently seeded walks tried before giving up
+const KANGAROO_ATTEMPTS: u32 = 4;
+
+/// Pollard's kangaroo (lambda) algorithm.
+/// Finds x in [lower, upper] such that `target` = x * `generator`, using O(sqrt(upper - lower)) group operations.
+pub fn kangaroo<G: Group>(
+    group: &G,
+    generator: &G::Element,
+    target: &G::Element,
+    lower: &BigUint,
+    upper: &BigUint,
+) -> Option<BigUint> {
+    let width = upper - lower;
+
+    (0..KANGAROO_ATTEMPTS).find_map(|attempt| {
+        let jumps = JumpFunction::for_interval(&width).with_seed(attempt);
+        kangaroo_with_jumps(group, generator, target, lower, upper, &jumps)
+    })
+}
+
+/// A single kangaroo walk with the given jump function, which may fail with a small probability
+pub fn kangaroo_with_jumps<G: Group>(
+    group: &G,
+    generator: &G::Element,
+    target: &G::Element,
+    lower: &BigUint,
+    upper: &BigUint,
+    jumps: &JumpFunction,
+) -> Option<BigUint> {
+    let width = upper - lower;
+
+    let jump_sizes = jumps.sizes();
+    let jump_elements = jump_sizes
+        .iter()
+        .map(|size| group.scalar(generator, size))
+        .collect::<Vec<_>>();
+    let steps = (jumps.mean() * 4_u32).to_u64().unwrap_or(u64::MAX);
+
+    let jump_index = |y: &G::Element| jumps.index(group.element_hash(y));
+
+    // The tame kangaroo sets a trap at a known distance beyond upper
+    let mut tame_distance = BigUint::zero();
+    let mut tame = group.scalar(generator, upper);
+    for _ in 0..steps {
+        let i = jump_index(&tame);
+        tame_distance += &jump_sizes[i];
+        tame = group.operate(&tame, &jump_elements[i]);
+    }
+
+    // The wild kangaroo starts at the target and either lands in the trap or runs past it
+    let limit = &width + &tame_distance;
+    let mut wild_distance = BigUint::zero();
+    let mut wild = target.clone();
+    while wild_distance <= limit {
+        if wild == tame {
+            return Some(upper + tame_distance - wild_distance);
+        }
+
+        let i = jump_index(&wild);
+        wild_distance += &jump_sizes[i];
+        wild = group.operate(&wild, &jump_elements[i]);
+    }
+
+    None
+}
+
+/// Pohlig-Hellman.
+/// `generator` must have order n = product of r^e over `order_factors`, given as (r, e) with distinct primes r.
+/// Solves the logarithm in every prime power subgroup digit by digit with baby-step giant-step, and returns
+/// (x mod n, n) recombined with CRT.
+pub fn pohlig_hellman<G: Group>(
+    group: &G,
+    generator: &G::Element,
+    target: &G::Element,
+    order_factors: &[(u64, u32)],
+) -> Option<(BigUint, BigUint)> {
+    let order: BigUint = order_factors
+        .iter()
+        .map(|&(r, e)| BigUint::from(r).pow(e))
+        .product();
+
+    let congruences = order_factors
+        .iter()
+        .map(|&(r, e)| {
+            let r = BigUint::from(r);
+            let gamma = group.scalar(generator, &(&order / &r));
+
+            // x = d_0 + d_1 r + ... + d_(e-1) r^(e-1) (mod r^e)
+            let mut x = BigUint::zero();
+            let mut r_power = BigUint::one();
+            for _ in 0..e {
+                // (n / r^(k+1)) * (target - x * generator) = d_k * gamma, where -x = n - x in the subgroup
+                let shifted = group.operate(target, &group.scalar(generator, &((&order - &x) % &order)));
+                let h = group.scalar(&shifted, &(&order / (&r_power * &r)));
+
+                let digit = baby_step_giant_step(group, &gamma, &h, &r)?;
+                x += digit * &r_power;
+                r_power *= &r;
+            }
+
+            Some((x, r_power))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(crt(&congruences))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diffie_hellman::DhGroup;
+    use crate::elliptic_curve::EcGroup;
+    use crate::number_theory::random_in_range;
+
+    #[test]
+    fn test_baby_step_giant_step() {
+        let group = EcGroup::cryptopals_p128();
+
+        let upper = BigUint::from(1_u32 << 20);
+        let secret = random_in_range(&BigUint::zero(), &upper);
+        let target = group.scalar_base_mul(&secret);
+
+        let found = baby_step_giant_step(&group.curve, &group.generator, &target, &upper);
+
+        assert_eq!(found, Some(secret));
+    }
+
+    #[test]
+    fn test_kangaroo_elliptic_curve() {
+        let group = EcGroup::cryptopals_p128();
+
+        let lower = BigUint::from(1_u32 << 10);
+        let upper = BigUint::from(1_u32 << 20);
+        let secret = random_in_range(&lower, &upper);
+        let target = group.scalar_base_mul(&secret);
+
+        let found = kangaroo(&group.curve, &group.generator, &target, &lower, &upper);
+
+        assert_eq!(found, Some(secret));
+    }
+
+    #[test]
+    fn test_kangaroo_custom_jumps() {
+        let group = EcGroup::cryptopals_p128();
+
+        let upper = BigUint::from(1_u32 << 16);
+        let secret = random_in_range(&BigUint::zero(), &upper);
+        let target = group.scalar_base_mul(&secret);
+
+        let sizes = [1_u32, 3, 7, 20, 51, 120, 260].map(BigUint::from).to_vec();
+        let found = (0..KANGAROO_ATTEMPTS).find_map(|seed| {
+            let jumps = JumpFunction::new(sizes.clone()).with_seed(seed);
+            kangaroo_with_jumps(&group.curve, &group.generator, &target, &BigUint::zero(), &upper, &jumps)
+        });
+
+        assert_eq!(found, Some(secret));
+    }
+
+    #[test]
+    fn test_pohlig_hellman() {
+        let group = DhGroup::cryptopals_57();
+        let order_factors = [(2, 1), (3, 2), (5, 1), (109, 1), (7963, 1)];
+        let order: BigUint = [2_u32, 9, 5, 109, 7963].iter().product();
+
+        // An element of order exactly 2 * 3^2 * 5 * 109 * 7963
+        let cofactor = (&group.p - 1_u32) / &order;
+        let generator = loop {
+            let h = group.scalar(&random_in_range(&BigUint::from(2_u32), &group.p), &cofactor);
+            if order_factors
+                .iter()
+                .all(|&(r, _)| !group.scalar(&h, &(&order / r)).is_one())
+            {
+                break h;
+            }
+        };
+
+        let secret = random_in_range(&BigUint::zero(), &order);
+        let target = group.scalar(&generator, &secret);
+
+        assert_eq!(
+            pohlig_hellman(&group, &generator, &target, &order_factors),
+            Some((secret, order))
+        );
+    }
+}
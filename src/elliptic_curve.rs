@@ -0,0 +1,584 @@
+//! Elliptic curves in short Weierstrass form y^2 = x^3 + ax + b and Montgomery form v^2 = u^3 + Au^2 + u
+//! over a prime field, ECDH and ECDSA
+//! https://en.wikipedia.org/wiki/Elliptic_curve_point_multiplication
+//! https://en.wikipedia.org/wiki/Montgomery_curve
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use openssl::sha::sha256;
+
+use crate::dlog::Group;
+use crate::number_theory::{is_quadratic_residue, mod_inverse, mod_sqrt, mod_sub, random_in_range};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Point {
+    Infinity,
+    Affine { x: BigUint, y: BigUint },
+}
+
+impl Point {
+    pub fn new(x: BigUint, y: BigUint) -> Self {
+        Self::Affine { x, y }
+    }
+
+    /// Serialization used for key derivation, x || y in big endian. The point at infinity is empty.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Point::Infinity => vec![],
+            Point::Affine { x, y } => {
+                let mut bytes = x.to_bytes_be();
+                bytes.extend_from_slice(&y.to_bytes_be());
+                bytes
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeierstrassCurve {
+    pub a: BigUint,
+    pub b: BigUint,
+    pub p: BigUint,
+}
+
+impl WeierstrassCurve {
+    pub fn new(a: BigUint, b: BigUint, p: BigUint) -> Self {
+        Self { a, b, p }
+    }
+
+    /// Same curve with another b. Point addition never uses b, so points of this curve are accepted by
+    /// implementations of the original curve that skip validation.
+    pub fn with_b(&self, b: BigUint) -> Self {
+        Self::new(self.a.clone(), b, self.p.clone())
+    }
+
+    /// Right hand side x^3 + ax + b
+    fn rhs(&self, x: &BigUint) -> BigUint {
+        (x * x * x + &self.a * x + &self.b) % &self.p
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        match point {
+            Point::Infinity => true,
+            Point::Affine { x, y } => y * y % &self.p == self.rhs(x),
+        }
+    }
+
+    /// One of the two points with the given x coordinate, if any
+    pub fn lift_x(&self, x: &BigUint) -> Option<Point> {
+        mod_sqrt(&self.rhs(x), &self.p).map(|y| Point::new(x % &self.p, y))
+    }
+
+    pub fn random_point(&self) -> Point {
+        loop {
+            let x = random_in_range(&BigUint::zero(), &self.p);
+            if let Some(point) = self.lift_x(&x) {
+                return point;
+            }
+        }
+    }
+
+    pub fn negate(&self, point: &Point) -> Point {
+        match point {
+            Point::Infinity => Point::Infinity,
+            Point::Affine { x, y } => Point::new(x.clone(), mod_sub(&BigUint::zero(), y, &self.p)),
+        }
+    }
+
+    pub fn add(&self, first: &Point, second: &Point) -> Point {
+        let (x1, y1, x2, y2) = match (first, second) {
+            (Point::Infinity, _) => return second.clone(),
+            (_, Point::Infinity) => return first.clone(),
+            (Point::Affine { x: x1, y: y1 }, Point::Affine { x: x2, y: y2 }) => (x1, y1, x2, y2),
+        };
+
+        if *second == self.negate(first) {
+            return Point::Infinity;
+        }
+
+        let p = &self.p;
+        let m = if first == second {
+            // (3x^2 + a) / 2y
+            let numerator = (BigUint::from(3_u32) * x1 * x1 + &self.a) % p;
+            numerator * mod_inverse(&((y1 << 1_u32) % p), p).unwrap() % p
+        } else {
+            // (y2 - y1) / (x2 - x1)
+            mod_sub(y2, y1, p) * mod_inverse(&mod_sub(x2, x1, p), p).unwrap() % p
+        };
+
+        let x3 = mod_sub(&mod_sub(&(&m * &m % p), x1, p), x2, p);
+        let y3 = mod_sub(&(m * mod_sub(x1, &x3, p) % p), y1, p);
+
+        Point::new(x3, y3)
+    }
+
+    /// Double-and-add scalar multiplication
+    pub fn scalar_mul(&self, point: &Point, k: &BigUint) -> Point {
+        let mut result = Point::Infinity;
+        let mut addend = point.clone();
+
+        for i in 0..k.bits() {
+            if k.bit(i) {
+                result = self.add(&result, &addend);
+            }
+            addend = self.add(&addend, &addend);
+        }
+
+        result
+    }
+}
+
+impl Group for WeierstrassCurve {
+    type Element = Point;
+
+    fn identity(&self) -> Point {
+        Point::Infinity
+    }
+
+    fn operate(&self, a: &Point, b: &Point) -> Point {
+        self.add(a, b)
+    }
+
+    fn scalar(&self, a: &Point, k: &BigUint) -> Point {
+        self.scalar_mul(a, k)
+    }
+
+    fn element_hash(&self, a: &Point) -> u64 {
+        match a {
+            Point::Infinity => 0,
+            Point::Affine { x, .. } => x.iter_u64_digits().next().unwrap_or(0),
+        }
+    }
+}
+
+/// A group together with a base point of prime order
+#[derive(Debug, Clone)]
+pub struct EcGroup {
+    pub curve: WeierstrassCurve,
+    pub generator: Point,
+    pub order: BigUint,
+}
+
+impl EcGroup {
+    /// y^2 = x^3 - 95051x + 11279326 over a 128 bit prime, with a base point of order q.
+    /// The curve has order 8q.
+    pub fn cryptopals_p128() -> Self {
+        let p = BigUint::parse_bytes(b"233970423115425145524320034830162017933", 10).unwrap();
+        let a = &p - 95051_u32;
+        let b = BigUint::from(11279326_u32);
+
+        let generator = Point::new(
+            BigUint::from(182_u32),
+            BigUint::parse_bytes(b"85518893674295321206118380980485522083", 10).unwrap(),
+        );
+        let order = BigUint::parse_bytes(b"29246302889428143187362802287225875743", 10).unwrap();
+
+        Self {
+            curve: WeierstrassCurve::new(a, b, p),
+            generator,
+            order,
+        }
+    }
+
+    pub fn scalar_base_mul(&self, k: &BigUint) -> Point {
+        self.curve.scalar_mul(&self.generator, k)
+    }
+}
+
+pub struct EcdhKeyPair {
+    secret: BigUint,
+    pub public: Point,
+}
+
+impl EcdhKeyPair {
+    pub fn generate(group: &EcGroup) -> Self {
+        let secret = random_in_range(&BigUint::one(), &group.order);
+        let public = group.scalar_base_mul(&secret);
+
+        Self { secret, public }
+    }
+
+    /// Computes the shared point without validating the peer's public key
+    pub fn shared_secret(&self, group: &EcGroup, peer_public: &Point) -> Point {
+        group.curve.scalar_mul(peer_public, &self.secret)
+    }
+
+    /// Only for verifying attacks
+    pub(crate) fn secret(&self) -> &BigUint {
+        &self.secret
+    }
+}
+
+/// Leftmost bits of SHA-256(message), as many as the group order has
+/// https://en.wikipedia.org/wiki/Elliptic_Curve_Digital_Signature_Algorithm
+pub fn ecdsa_hash(group: &EcGroup, message: &[u8]) -> BigUint {
+    let hash = BigUint::from_bytes_be(&sha256(message));
+    let excess_bits = (256 - group.order.bits() as i64).max(0);
+
+    hash >> excess_bits
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcdsaSignature {
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+pub struct EcdsaKeyPair {
+    secret: BigUint,
+    pub public: Point,
+}
+
+impl EcdsaKeyPair {
+    pub fn generate(group: &EcGroup) -> Self {
+        Self::from_secret(group, random_in_range(&BigUint::one(), &group.order))
+    }
+
+    pub fn from_secret(group: &EcGroup, secret: BigUint) -> Self {
+        let public = group.scalar_base_mul(&secret);
+
+        Self { secret, public }
+    }
+
+    pub fn sign(&self, group: &EcGroup, message: &[u8]) -> EcdsaSignature {
+        loop {
+            let nonce = random_in_range(&BigUint::one(), &group.order);
+            if let Some(signature) = self.sign_with_nonce(group, message, &nonce) {
+                return signature;
+            }
+        }
+    }
+
+    /// Signs with the given nonce k, failing for the rare k giving r = 0 or s = 0
+    pub fn sign_with_nonce(
+        &self,
+        group: &EcGroup,
+        message: &[u8],
+        nonce: &BigUint,
+    ) -> Option<EcdsaSignature> {
+        let n = &group.order;
+
+        let r = match group.scalar_base_mul(nonce) {
+            Point::Affine { x, .. } => x % n,
+            Point::Infinity => return None,
+        };
+
+        // s = (H(m) + r d) / k
+        let s = (ecdsa_hash(group, message) + &r * &self.secret) * mod_inverse(nonce, n)? % n;
+
+        (!r.is_zero() && !s.is_zero()).then(|| EcdsaSignature { r, s })
+    }
+
+    /// Only for verifying attacks
+    pub(crate) fn secret(&self) -> &BigUint {
+        &self.secret
+    }
+}
+
+pub fn ecdsa_verify(group: &EcGroup, public: &Point, message: &[u8], signature: &EcdsaSignature) -> bool {
+    let n = &group.order;
+    let EcdsaSignature { r, s } = signature;
+    if r.is_zero() || r >= n || s.is_zero() || s >= n {
+        return false;
+    }
+
+    let s_inverse = match mod_inverse(s, n) {
+        Some(s_inverse) => s_inverse,
+        None => return false,
+    };
+    let u1 = ecdsa_hash(group, message) * &s_inverse % n;
+    let u2 = r * &s_inverse % n;
+
+    match group.curve.add(&group.scalar_base_mul(&u1), &group.curve.scalar_mul(public, &u2)) {
+        Point::Affine { x, .. } => x % n == *r,
+        Point::Infinity => false,
+    }
+}
+
+/// Montgomery curve v^2 = u^3 + Au^2 + u using x-only (u coordinate) arithmetic.
+/// The point at infinity is represented by u = 0, which is otherwise the point of order 2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MontgomeryCurve {
+    pub a: BigUint,
+    pub p: BigUint,
+}
+
+/// Projective x-only coordinates (X : Z) with u = X / Z
+type ProjectiveU = (BigUint, BigUint);
+
+impl MontgomeryCurve {
+    pub fn new(a: BigUint, p: BigUint) -> Self {
+        Self { a, p }
+    }
+
+    /// Right hand side u^3 + Au^2 + u
+    fn rhs(&self, u: &BigUint) -> BigUint {
+        (u * u * u + &self.a * u * u + u) % &self.p
+    }
+
+    /// True if u is a coordinate of a point on the curve, false if it belongs to the quadratic twist
+    pub fn contains(&self, u: &BigUint) -> bool {
+        let rhs = self.rhs(u);
+        rhs.is_zero() || is_quadratic_residue(&rhs, &self.p)
+    }
+
+    pub fn random_twist_u(&self) -> BigUint {
+        loop {
+            let u = random_in_range(&BigUint::zero(), &self.p);
+            if !self.contains(&u) {
+                return u;
+            }
+        }
+    }
+
+    /// The x coordinate of the corresponding point on the birationally equivalent Weierstrass curve,
+    /// x = u + A/3
+    pub fn to_weierstrass_x(&self, u: &BigUint) -> BigUint {
+        let a_third = &self.a * mod_inverse(&BigUint::from(3_u32), &self.p).unwrap() % &self.p;
+
+        (u + a_third) % &self.p
+    }
+
+    fn to_affine(&self, (x, z): &ProjectiveU) -> BigUint {
+        match mod_inverse(z, &self.p) {
+            Some(z_inverse) => x * z_inverse % &self.p,
+            None => BigUint::zero(),
+        }
+    }
+
+    fn x_double(&self, (x, z): &ProjectiveU) -> ProjectiveU {
+        let p = &self.p;
+        let sum_squared = (x + z) * (x + z) % p;
+        let difference = mod_sub(x, z, p);
+        let difference_squared = &difference * &difference % p;
+        let four_xz = mod_sub(&sum_squared, &difference_squared, p);
+
+        // a24 = (A + 2) / 4
+        let a24 = (&self.a + 2_u32) * mod_inverse(&BigUint::from(4_u32), p).unwrap() % p;
+
+        (
+            &sum_squared * &difference_squared % p,
+            &four_xz * ((difference_squared + a24 * &four_xz) % p) % p,
+        )
+    }
+
+    /// Differential addition of P_m and P_n given P_(m - n)
+    fn x_add(&self, (xm, zm): &ProjectiveU, (xn, zn): &ProjectiveU, (xd, zd): &ProjectiveU) -> ProjectiveU {
+        let p = &self.p;
+        let u = mod_sub(xm, zm, p) * ((xn + zn) % p) % p;
+        let v = ((xm + zm) % p) * mod_sub(xn, zn, p) % p;
+
+        let sum = (&u + &v) % p;
+        let difference = mod_sub(&u, &v, p);
+
+        (zd * &sum * &sum % p, xd * &difference * &difference % p)
+    }
+
+    /// Montgomery ladder, the u coordinate of k * P given the u coordinate of P
+    pub fn ladder(&self, u: &BigUint, k: &BigUint) -> BigUint {
+        let p = &self.p;
+        let (mut u2, mut w2) = (BigUint::one(), BigUint::zero());
+        let (mut u3, mut w3) = (u.clone(), BigUint::one());
+
+        for i in (0..p.bits()).rev() {
+            let bit = k.bit(i);
+            if bit {
+                std::mem::swap(&mut u2, &mut u3);
+                std::mem::swap(&mut w2, &mut w3);
+            }
+
+            let (x3, z3) = (
+                mod_sub(&(&u2 * &u3 % p), &(&w2 * &w3 % p), p),
+                mod_sub(&(&u2 * &w3 % p), &(&w2 * &u3 % p), p),
+            );
+            let next_u3 = &x3 * &x3 % p;
+            let next_w3 = u * &z3 * &z3 % p;
+
+            let squares_difference = mod_sub(&(&u2 * &u2 % p), &(&w2 * &w2 % p), p);
+            let next_u2 = &squares_difference * &squares_difference % p;
+            let next_w2 = BigUint::from(4_u32) * &u2 * &w2 % p
+                * ((&u2 * &u2 + &self.a * &u2 * &w2 + &w2 * &w2) % p)
+                % p;
+
+            u2 = next_u2;
+            w2 = next_w2;
+            u3 = next_u3;
+            w3 = next_w3;
+
+            if bit {
+                std::mem::swap(&mut u2, &mut u3);
+                std::mem::swap(&mut w2, &mut w3);
+            }
+        }
+
+        self.to_affine(&(u2, w2))
+    }
+
+    /// The u coordinates of P, 2P, 3P, ... by differential addition
+    pub fn multiples<'a>(&'a self, u: &BigUint) -> impl Iterator<Item = BigUint> + 'a {
+        let base: ProjectiveU = (u.clone(), BigUint::one());
+        let mut previous = base.clone();
+        let mut current = self.x_double(&base);
+        let mut first = true;
+
+        std::iter::from_fn(move || {
+            if first {
+                first = false;
+                return Some(self.to_affine(&previous));
+            }
+
+            let next = self.x_add(&current, &base, &previous);
+            let u = self.to_affine(&current);
+            previous = std::mem::replace(&mut current, next);
+
+            Some(u)
+        })
+    }
+}
+
+/// A Montgomery curve together with the u coordinate of a base point of prime order
+#[derive(Debug, Clone)]
+pub struct MontgomeryGroup {
+    pub curve: MontgomeryCurve,
+    pub base_u: BigUint,
+    pub order: BigUint,
+}
+
+impl MontgomeryGroup {
+    /// v^2 = u^3 + 534u^2 + u, equivalent to `EcGroup::cryptopals_p128` with u = x - 178
+    pub fn cryptopals_p128() -> Self {
+        let p = BigUint::parse_bytes(b"233970423115425145524320034830162017933", 10).unwrap();
+        let order = BigUint::parse_bytes(b"29246302889428143187362802287225875743", 10).unwrap();
+
+        Self {
+            curve: MontgomeryCurve::new(BigUint::from(534_u32), p),
+            base_u: BigUint::from(4_u32),
+            order,
+        }
+    }
+
+    /// Order of the curve, the base point having cofactor 8
+    pub fn curve_order(&self) -> BigUint {
+        &self.order * 8_u32
+    }
+
+    /// Order of the quadratic twist, 2p + 2 - n
+    pub fn twist_order(&self) -> BigUint {
+        ((&self.curve.p + 1_u32) << 1_u32) - self.curve_order()
+    }
+}
+
+pub struct MontgomeryKeyPair {
+    secret: BigUint,
+    pub public: BigUint,
+}
+
+impl MontgomeryKeyPair {
+    pub fn generate(group: &MontgomeryGroup) -> Self {
+        let secret = random_in_range(&BigUint::one(), &group.order);
+        let public = group.curve.ladder(&group.base_u, &secret);
+
+        Self { secret, public }
+    }
+
+    /// x-only ECDH, the peer's u coordinate is not validated
+    pub fn shared_secret(&self, group: &MontgomeryGroup, peer_public: &BigUint) -> BigUint {
+        group.curve.ladder(peer_public, &self.secret)
+    }
+
+    /// Only for verifying attacks
+    pub(crate) fn secret(&self) -> &BigUint {
+        &self.secret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ec_generator_order() {
+        let group = EcGroup::cryptopals_p128();
+
+        assert!(group.curve.contains(&group.generator));
+        assert_eq!(group.scalar_base_mul(&group.order), Point::Infinity);
+    }
+
+    #[test]
+    fn test_ec_add() {
+        let group = EcGroup::cryptopals_p128();
+        let curve = &group.curve;
+        let g = &group.generator;
+
+        let g2 = curve.add(g, g);
+        let g3 = curve.add(&g2, g);
+        assert!(curve.contains(&g3));
+        assert_eq!(curve.scalar_mul(g, &BigUint::from(3_u32)), g3);
+        assert_eq!(curve.add(&g3, &curve.negate(&g2)), *g);
+        assert_eq!(curve.add(g, &curve.negate(g)), Point::Infinity);
+    }
+
+    #[test]
+    fn test_ecdh() {
+        let group = EcGroup::cryptopals_p128();
+
+        let alice = EcdhKeyPair::generate(&group);
+        let bob = EcdhKeyPair::generate(&group);
+
+        assert_eq!(
+            alice.shared_secret(&group, &bob.public),
+            bob.shared_secret(&group, &alice.public)
+        );
+    }
+
+    #[test]
+    fn test_ecdsa() {
+        let group = EcGroup::cryptopals_p128();
+        let key_pair = EcdsaKeyPair::generate(&group);
+
+        let signature = key_pair.sign(&group, b"hi mom");
+
+        assert!(ecdsa_verify(&group, &key_pair.public, b"hi mom", &signature));
+        assert!(!ecdsa_verify(&group, &key_pair.public, b"hi dad", &signature));
+        assert!(!ecdsa_verify(&group, &EcdsaKeyPair::generate(&group).public, b"hi mom", &signature));
+    }
+
+    #[test]
+    fn test_montgomery_ladder() {
+        let group = MontgomeryGroup::cryptopals_p128();
+        let weierstrass = EcGroup::cryptopals_p128();
+
+        assert_eq!(group.curve.to_weierstrass_x(&group.base_u), BigUint::from(182_u32));
+        assert!(group.curve.ladder(&group.base_u, &group.order).is_zero());
+
+        let k = random_in_range(&BigUint::one(), &group.order);
+        let u = group.curve.ladder(&group.base_u, &k);
+        match weierstrass.scalar_base_mul(&k) {
+            Point::Affine { x, .. } => assert_eq!(group.curve.to_weierstrass_x(&u), x),
+            Point::Infinity => panic!("Unexpected point at infinity"),
+        }
+    }
+
+    #[test]
+    fn test_montgomery_multiples() {
+        let group = MontgomeryGroup::cryptopals_p128();
+        let u = group.curve.random_twist_u();
+
+        for (k, multiple) in (1..20_u32).zip(group.curve.multiples(&u)) {
+            assert_eq!(multiple, group.curve.ladder(&u, &BigUint::from(k)));
+        }
+    }
+
+    #[test]
+    fn test_montgomery_ecdh() {
+        let group = MontgomeryGroup::cryptopals_p128();
+
+        let alice = MontgomeryKeyPair::generate(&group);
+        let bob = MontgomeryKeyPair::generate(&group);
+
+        assert_eq!(
+            alice.shared_secret(&group, &bob.public),
+            bob.shared_secret(&group, &alice.public)
+        );
+    }
+}
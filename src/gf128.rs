@@ -0,0 +1,461 @@
+//! Arithmetic in GF(2^128) as used by GCM, and polynomials over it
+//! https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38d.pdf
+
+use std::ops::{Add, Mul, Sub};
+
+use rand::{thread_rng, Rng};
+
+use crate::aes::AES_BLOCK_SIZE;
+use crate::gf2::{Gf2Matrix, Gf2Vector};
+
+/// Reduction of x^128 = x^7 + x^2 + x + 1
+const REDUCTION: u128 = 0x87;
+
+/// Element of GF(2^128) modulo x^128 + x^7 + x^2 + x + 1, bit i being the coefficient of x^i
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Gf128(pub u128);
+
+impl Gf128 {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1);
+
+    /// GCM blocks store the coefficient of x^0 in the most significant bit of the first byte.
+    /// Short blocks are zero padded on the right.
+    pub fn from_block(block: &[u8]) -> Self {
+        assert!(block.len() <= AES_BLOCK_SIZE);
+
+        let mut buffer = [0_u8; AES_BLOCK_SIZE];
+        buffer[..block.len()].copy_from_slice(block);
+
+        Self(u128::from_be_bytes(buffer).reverse_bits())
+    }
+
+    pub fn to_block(self) -> [u8; AES_BLOCK_SIZE] {
+        self.0.reverse_bits().to_be_bytes()
+    }
+
+    pub fn random() -> Self {
+        Self(thread_rng().gen())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn square(self) -> Self {
+        self * self
+    }
+
+    pub fn pow(self, exponent: u128) -> Self {
+        let mut result = Self::ONE;
+        let mut base = self;
+        let mut exponent = exponent;
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base.square();
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    pub fn to_vector(self) -> Gf2Vector {
+        Gf2Vector::from_u128(self.0, 128)
+    }
+
+    pub fn from_vector(vector: &Gf2Vector) -> Self {
+        Self(vector.to_u128())
+    }
+
+    /// Matrix over GF(2) of the linear map x -> self * x
+    pub fn multiplication_matrix(self) -> Gf2Matrix {
+        let columns = (0..128)
+            .map(|j| (self * Self(1 << j)).to_vector())
+            .collect::<Vec<_>>();
+
+        Gf2Matrix::from_columns(128, &columns)
+    }
+
+    /// Matrix over GF(2) of the linear map x -> x^2, which is linear in characteristic 2
+    pub fn squaring_matrix() -> Gf2Matrix {
+        let columns = (0..128)
+            .map(|j| Self(1 << j).square().to_vector())
+            .collect::<Vec<_>>();
+
+        Gf2Matrix::from_columns(128, &columns)
+    }
+
+    /// Multiplicative inverse by Fermat, a^(2^128 - 2)
+    pub fn inverse(self) -> Self {
+        assert!(!self.is_zero(), "Zero has no inverse");
+        self.pow(u128::MAX - 1)
+    }
+}
+
+impl Add for Gf128 {
+    type Output = Self;
+
+    /// Addition in characteristic 2 is xor
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+}
+
+impl Sub for Gf128 {
+    type Output = Self;
+
+    /// Subtraction and addition coincide in characteristic 2
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, other: Self) -> Self {
+        self + other
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Self;
+
+    /// Carry-less shift-and-add multiplication with reduction on every shift
+    fn mul(self, other: Self) -> Self {
+        let mut a = self.0;
+        let mut result = 0_u128;
+
+        for i in 0..128 {
+            if (other.0 >> i) & 1 == 1 {
+                result ^= a;
+            }
+
+            let carry = a >> 127;
+            a <<= 1;
+            if carry == 1 {
+                a ^= REDUCTION;
+            }
+        }
+
+        Self(result)
+    }
+}
+
+/// Polynomial with coefficients in GF(2^128), lowest degree first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gf128Polynomial {
+    coefficients: Vec<Gf128>,
+}
+
+impl Gf128Polynomial {
+    pub fn new(coefficients: Vec<Gf128>) -> Self {
+        let mut polynomial = Self { coefficients };
+        polynomial.trim();
+
+        polynomial
+    }
+
+    pub fn zero() -> Self {
+        Self::new(vec![])
+    }
+
+    pub fn one() -> Self {
+        Self::new(vec![Gf128::ONE])
+    }
+
+    /// The polynomial x
+    pub fn x() -> Self {
+        Self::new(vec![Gf128::ZERO, Gf128::ONE])
+    }
+
+    /// The polynomial (x - a_1)(x - a_2)...(x - a_n)
+    pub fn from_roots(roots: &[Gf128]) -> Self {
+        roots.iter().fold(Self::one(), |acc, &root| {
+            acc * Self::new(vec![root, Gf128::ONE])
+        })
+    }
+
+    fn trim(&mut self) {
+        while self.coefficients.last().map_or(false, |c| c.is_zero()) {
+            self.coefficients.pop();
+        }
+    }
+
+    pub fn coefficients(&self) -> &[Gf128] {
+        &self.coefficients
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// Degree of the polynomial, None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn leading_coefficient(&self) -> Gf128 {
+        self.coefficients.last().cloned().unwrap_or(Gf128::ZERO)
+    }
+
+    pub fn evaluate(&self, x: Gf128) -> Gf128 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Gf128::ZERO, |acc, &c| acc * x + c)
+    }
+
+    pub fn scale(&self, factor: Gf128) -> Self {
+        Self::new(self.coefficients.iter().map(|&c| c * factor).collect())
+    }
+
+    pub fn monic(&self) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+
+        self.scale(self.leading_coefficient().inverse())
+    }
+
+    /// Long division, returns (quotient, remainder)
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let divisor_degree = divisor.degree().expect("Division by the zero polynomial");
+        let inverse_lead = divisor.leading_coefficient().inverse();
+
+        let mut remainder = self.coefficients.clone();
+        let quotient_len = remainder.len().saturating_sub(divisor_degree);
+        let mut quotient = vec![Gf128::ZERO; quotient_len];
+
+        for i in (0..quotient_len).rev() {
+            let factor = remainder[i + divisor_degree] * inverse_lead;
+            if factor.is_zero() {
+                continue;
+            }
+
+            quotient[i] = factor;
+            for (j, &d) in divisor.coefficients.iter().enumerate() {
+                remainder[i + j] = remainder[i + j] + factor * d;
+            }
+        }
+
+        (Self::new(quotient), Self::new(remainder))
+    }
+
+    pub fn rem(&self, modulus: &Self) -> Self {
+        self.div_rem(modulus).1
+    }
+
+    /// Monic greatest common divisor
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.clone();
+        let mut b = other.clone();
+
+        while !b.is_zero() {
+            let r = a.rem(&b);
+            a = b;
+            b = r;
+        }
+
+        a.monic()
+    }
+
+    pub fn derivative(&self) -> Self {
+        // Characteristic 2: the terms of even degree vanish
+        Self::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &c)| if i % 2 == 1 { c } else { Gf128::ZERO })
+                .collect(),
+        )
+    }
+
+    /// Computes self^(2^k) mod modulus by repeated squaring
+    fn frobenius_mod(&self, k: usize, modulus: &Self) -> Self {
+        (0..k).fold(self.rem(modulus), |acc, _| (&acc * &acc).rem(modulus))
+    }
+
+    /// The distinct roots of the polynomial in GF(2^128)
+    pub fn roots(&self) -> Vec<Gf128> {
+        if self.degree().unwrap_or(0) == 0 {
+            return vec![];
+        }
+
+        // The product of all linear factors is gcd(f, x^q - x) with q = 2^128
+        let f = self.monic();
+        let x_q = Self::x().frobenius_mod(128, &f);
+        let linear_factors = f.gcd(&(x_q + Self::x()));
+
+        let mut roots = vec![];
+        linear_factors.split_linear_factors(&mut roots);
+
+        roots
+    }
+
+    /// Cantor-Zassenhaus equal-degree splitting of a squarefree product of linear factors.
+    /// In characteristic 2 the trace map Tr(a*x) splits the roots in two halves.
+    fn split_linear_factors(&self, roots: &mut Vec<Gf128>) {
+        match self.degree() {
+            None | Some(0) => {}
+            Some(1) => roots.push(self.monic().coefficients[0]),
+            _ => loop {
+                let a_x = Self::new(vec![Gf128::ZERO, Gf128::random()]);
+
+                let mut trace = a_x.clone();
+                let mut term = a_x;
+                for _ in 1..128 {
+                    term = (&term * &term).rem(self);
+                    trace = trace + term.clone();
+                }
+
+                let factor = self.gcd(&trace);
+                let factor_degree = factor.degree().unwrap_or(0);
+                if factor_degree > 0 && Some(factor_degree) < self.degree() {
+                    let (cofactor, _) = self.div_rem(&factor);
+                    factor.split_linear_factors(roots);
+                    cofactor.split_linear_factors(roots);
+                    return;
+                }
+            },
+        }
+    }
+}
+
+impl Add for Gf128Polynomial {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (mut long, short) = if self.coefficients.len() >= other.coefficients.len() {
+            (self.coefficients, other.coefficients)
+        } else {
+            (other.coefficients, self.coefficients)
+        };
+
+        long.iter_mut().zip(short).for_each(|(a, b)| *a = *a + b);
+
+        Self::new(long)
+    }
+}
+
+impl Sub for Gf128Polynomial {
+    type Output = Self;
+
+    /// Subtraction and addition coincide in characteristic 2
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, other: Self) -> Self {
+        self + other
+    }
+}
+
+impl Mul for &Gf128Polynomial {
+    type Output = Gf128Polynomial;
+
+    fn mul(self, other: Self) -> Gf128Polynomial {
+        if self.is_zero() || other.is_zero() {
+            return Gf128Polynomial::zero();
+        }
+
+        let mut coefficients =
+            vec![Gf128::ZERO; self.coefficients.len() + other.coefficients.len() - 1];
+
+        for (i, &a) in self.coefficients.iter().enumerate() {
+            for (j, &b) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = coefficients[i + j] + a * b;
+            }
+        }
+
+        Gf128Polynomial::new(coefficients)
+    }
+}
+
+impl Mul for Gf128Polynomial {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        &self * &other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf128_matrices() {
+        let a = Gf128::random();
+        let b = Gf128::random();
+
+        assert_eq!(
+            Gf128::from_vector(&a.multiplication_matrix().mul_vector(&b.to_vector())),
+            a * b
+        );
+        assert_eq!(
+            Gf128::from_vector(&Gf128::squaring_matrix().mul_vector(&b.to_vector())),
+            b.square()
+        );
+    }
+
+    #[test]
+    fn test_gf128_block_roundtrip() {
+        let block = hex::decode("66e94bd4ef8a2c3b884cfa59ca342b2e").unwrap();
+        assert_eq!(Gf128::from_block(&block).to_block().to_vec(), block);
+
+        // The first bit of the block is the coefficient of x^0
+        assert_eq!(Gf128::from_block(&[0x80]), Gf128::ONE);
+    }
+
+    #[test]
+    fn test_gf128_mul() {
+        let a = Gf128::random();
+        let b = Gf128::random();
+        let c = Gf128::random();
+
+        assert_eq!(a * Gf128::ONE, a);
+        assert_eq!(a * b, b * a);
+        assert_eq!(a * (b + c), a * b + a * c);
+        assert_eq!((a * b) * c, a * (b * c));
+
+        // x^127 * x = x^128 = x^7 + x^2 + x + 1
+        assert_eq!(Gf128(1 << 127) * Gf128(2), Gf128(REDUCTION));
+    }
+
+    #[test]
+    fn test_gf128_inverse() {
+        let a = Gf128::random();
+        assert_eq!(a * a.inverse(), Gf128::ONE);
+    }
+
+    #[test]
+    fn test_gf128_polynomial_div_rem() {
+        let a = Gf128Polynomial::new((0..5).map(|_| Gf128::random()).collect());
+        let b = Gf128Polynomial::new((0..3).map(|_| Gf128::random()).collect());
+
+        let (q, r) = a.div_rem(&b);
+        assert!(r.degree() < b.degree());
+        assert_eq!(&q * &b + r, a);
+    }
+
+    #[test]
+    fn test_gf128_polynomial_roots() {
+        let roots = (0..4).map(|_| Gf128::random()).collect::<Vec<_>>();
+
+        // Multiply by x^2 + x + c, which is irreducible exactly when the trace of c is 1
+        let noise = loop {
+            let c = Gf128::random();
+            let (trace, _) = (0..128).fold((Gf128::ZERO, c), |(sum, power), _| {
+                (sum + power, power.square())
+            });
+            if trace == Gf128::ONE {
+                break Gf128Polynomial::new(vec![c, Gf128::ONE, Gf128::ONE]);
+            }
+        };
+        let polynomial = Gf128Polynomial::from_roots(&roots) * noise;
+
+        let mut found = polynomial.roots();
+        found.sort_by_key(|r| r.0);
+
+        let mut expected = roots;
+        expected.sort_by_key(|r| r.0);
+
+        assert_eq!(found, expected);
+    }
+}
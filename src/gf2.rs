@@ -0,0 +1,469 @@
+//! Vectors and matrices over GF(2), packed 64 entries per word
+//! https://en.wikipedia.org/wiki/GF(2)
+
+use rand::{thread_rng, Rng};
+
+const WORD_BITS: usize = 64;
+
+fn word_count(len: usize) -> usize {
+    (len + WORD_BITS - 1) / WORD_BITS
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Gf2Vector {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl Gf2Vector {
+    pub fn zero(len: usize) -> Self {
+        Self {
+            len,
+            words: vec![0; word_count(len)],
+        }
+    }
+
+    pub fn random(len: usize) -> Self {
+        let mut vector = Self::zero(len);
+        thread_rng().fill(vector.words.as_mut_slice());
+        vector.clear_padding();
+
+        vector
+    }
+
+    /// The lowest `len` bits of `value`, bit i becoming entry i
+    pub fn from_u128(value: u128, len: usize) -> Self {
+        assert!(len <= 128);
+
+        let mut vector = Self::zero(len);
+        for (i, word) in vector.words.iter_mut().enumerate() {
+            *word = (value >> (i * WORD_BITS)) as u64;
+        }
+        vector.clear_padding();
+
+        vector
+    }
+
+    pub fn to_u128(&self) -> u128 {
+        assert!(self.len <= 128);
+
+        self.words
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &word)| acc | (word as u128) << (i * WORD_BITS))
+    }
+
+    pub fn from_bits(bits: &[bool]) -> Self {
+        let mut vector = Self::zero(bits.len());
+        for (i, &bit) in bits.iter().enumerate() {
+            vector.set(i, bit);
+        }
+
+        vector
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "Index out of bounds");
+        (self.words[i / WORD_BITS] >> (i % WORD_BITS)) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, bit: bool) {
+        assert!(i < self.len, "Index out of bounds");
+
+        let mask = 1 << (i % WORD_BITS);
+        if bit {
+            self.words[i / WORD_BITS] |= mask;
+        } else {
+            self.words[i / WORD_BITS] &= !mask;
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn xor_assign(&mut self, other: &Self) {
+        assert_eq!(self.len, other.len, "Vector lengths differ");
+
+        for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+            *word ^= other_word;
+        }
+    }
+
+    /// Inner product over GF(2)
+    pub fn dot(&self, other: &Self) -> bool {
+        assert_eq!(self.len, other.len, "Vector lengths differ");
+
+        let ones: u32 = self
+            .words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a & b).count_ones())
+            .sum();
+
+        ones % 2 == 1
+    }
+
+    /// Indices of the non-zero entries
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * WORD_BITS + bit)
+            })
+        })
+    }
+
+    /// Concatenation of `self` followed by `other`
+    pub fn concat(&self, other: &Self) -> Self {
+        let mut vector = Self::zero(self.len + other.len);
+        for i in self.ones() {
+            vector.set(i, true);
+        }
+        for i in other.ones() {
+            vector.set(self.len + i, true);
+        }
+
+        vector
+    }
+
+    fn clear_padding(&mut self) {
+        let used = self.len % WORD_BITS;
+        if used != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+}
+
+/// Row major matrix over GF(2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gf2Matrix {
+    cols: usize,
+    rows: Vec<Gf2Vector>,
+}
+
+impl Gf2Matrix {
+    pub fn zero(rows: usize, cols: usize) -> Self {
+        Self {
+            cols,
+            rows: vec![Gf2Vector::zero(cols); rows],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut matrix = Self::zero(n, n);
+        for i in 0..n {
+            matrix.set(i, i, true);
+        }
+
+        matrix
+    }
+
+    pub fn from_rows(cols: usize, rows: Vec<Gf2Vector>) -> Self {
+        assert!(
+            rows.iter().all(|row| row.len() == cols),
+            "Row lengths differ"
+        );
+
+        Self { cols, rows }
+    }
+
+    /// Matrix whose columns are the given vectors
+    pub fn from_columns(rows: usize, columns: &[Gf2Vector]) -> Self {
+        Self::from_rows(rows, columns.to_vec()).transpose()
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, i: usize) -> &Gf2Vector {
+        &self.rows[i]
+    }
+
+    pub fn rows(&self) -> &[Gf2Vector] {
+        &self.rows
+    }
+
+    pub fn column(&self, j: usize) -> Gf2Vector {
+        Gf2Vector::from_bits(&self.rows.iter().map(|row| row.get(j)).collect::<Vec<_>>())
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        self.rows[i].get(j)
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, bit: bool) {
+        self.rows[i].set(j, bit)
+    }
+
+    pub fn push_row(&mut self, row: Gf2Vector) {
+        assert_eq!(row.len(), self.cols, "Row length differs");
+        self.rows.push(row);
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut transposed = Self::zero(self.cols, self.num_rows());
+        for (i, row) in self.rows.iter().enumerate() {
+            for j in row.ones() {
+                transposed.set(j, i, true);
+            }
+        }
+
+        transposed
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        assert_eq!(
+            self.cols,
+            other.num_rows(),
+            "Incompatible matrix dimensions"
+        );
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut product = Gf2Vector::zero(other.cols);
+                for k in row.ones() {
+                    product.xor_assign(&other.rows[k]);
+                }
+                product
+            })
+            .collect();
+
+        Self::from_rows(other.cols, rows)
+    }
+
+    pub fn mul_vector(&self, vector: &Gf2Vector) -> Gf2Vector {
+        Gf2Vector::from_bits(
+            &self
+                .rows
+                .iter()
+                .map(|row| row.dot(vector))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        assert_eq!(
+            self.num_rows(),
+            other.num_rows(),
+            "Incompatible matrix dimensions"
+        );
+
+        let mut sum = self.clone();
+        for (row, other_row) in sum.rows.iter_mut().zip(other.rows.iter()) {
+            row.xor_assign(other_row);
+        }
+
+        sum
+    }
+
+    /// Reduced row echelon form by Gaussian elimination, together with the pivot column of every
+    /// non-zero row
+    pub fn row_reduce(&self) -> (Self, Vec<usize>) {
+        let mut reduced = self.clone();
+        let mut pivots = vec![];
+
+        for col in 0..self.cols {
+            let rank = pivots.len();
+            let pivot_row = match (rank..reduced.num_rows()).find(|&i| reduced.get(i, col)) {
+                Some(pivot_row) => pivot_row,
+                None => continue,
+            };
+            reduced.rows.swap(rank, pivot_row);
+
+            let pivot = reduced.rows[rank].clone();
+            for (i, row) in reduced.rows.iter_mut().enumerate() {
+                if i != rank && row.get(col) {
+                    row.xor_assign(&pivot);
+                }
+            }
+
+            pivots.push(col);
+        }
+
+        (reduced, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.row_reduce().1.len()
+    }
+
+    /// A solution of Mx = b with all free variables set to zero, or None if there is none. Only
+    /// eliminates below the pivots and back substitutes, which is about twice as fast as a full
+    /// `row_reduce`.
+    pub fn solve(&self, b: &Gf2Vector) -> Option<Gf2Vector> {
+        assert_eq!(b.len(), self.num_rows(), "Incompatible dimensions");
+
+        let mut rows = self.rows.clone();
+        let mut rhs = (0..b.len()).map(|i| b.get(i)).collect::<Vec<_>>();
+        let mut pivots = vec![];
+
+        for col in 0..self.cols {
+            let rank = pivots.len();
+            let pivot_row = match (rank..rows.len()).find(|&i| rows[i].get(col)) {
+                Some(pivot_row) => pivot_row,
+                None => continue,
+            };
+            rows.swap(rank, pivot_row);
+            rhs.swap(rank, pivot_row);
+
+            // Words before the pivot are zero in every remaining row
+            let first_word = col / WORD_BITS;
+            let (head, tail) = rows.split_at_mut(rank + 1);
+            let pivot = &head[rank].words[first_word..];
+            let pivot_rhs = rhs[rank];
+            for (row, bit) in tail.iter_mut().zip(rhs[rank + 1..].iter_mut()) {
+                if row.get(col) {
+                    for (word, pivot_word) in row.words[first_word..].iter_mut().zip(pivot) {
+                        *word ^= pivot_word;
+                    }
+                    *bit ^= pivot_rhs;
+                }
+            }
+
+            pivots.push(col);
+        }
+
+        if rhs[pivots.len()..].iter().any(|&bit| bit) {
+            return None;
+        }
+
+        let mut x = Gf2Vector::zero(self.cols);
+        for (i, &col) in pivots.iter().enumerate().rev() {
+            let value = rhs[i] ^ rows[i].dot(&x);
+            x.set(col, value);
+        }
+
+        Some(x)
+    }
+
+    /// Basis of the kernel {x : Mx = 0}
+    pub fn kernel(&self) -> Vec<Gf2Vector> {
+        let (reduced, pivots) = self.row_reduce();
+
+        (0..self.cols)
+            .filter(|col| !pivots.contains(col))
+            .map(|free| {
+                let mut vector = Gf2Vector::zero(self.cols);
+                vector.set(free, true);
+                for (row, &pivot) in pivots.iter().enumerate() {
+                    if reduced.get(row, free) {
+                        vector.set(pivot, true);
+                    }
+                }
+                vector
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&str]) -> Gf2Matrix {
+        let rows = rows
+            .iter()
+            .map(|row| Gf2Vector::from_bits(&row.chars().map(|c| c == '1').collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        Gf2Matrix::from_rows(rows[0].len(), rows)
+    }
+
+    #[test]
+    fn test_gf2_vector() {
+        let value = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210_u128;
+        let vector = Gf2Vector::from_u128(value, 128);
+
+        assert_eq!(vector.to_u128(), value);
+        assert_eq!(vector.ones().count(), value.count_ones() as usize);
+        assert_eq!(
+            Gf2Vector::from_u128(value, 70).to_u128(),
+            value & ((1 << 70) - 1)
+        );
+
+        let mut sum = vector.clone();
+        sum.xor_assign(&vector);
+        assert!(sum.is_zero());
+        assert!(!Gf2Vector::from_u128(0b1011, 4).dot(&Gf2Vector::from_u128(0b0011, 4)));
+    }
+
+    #[test]
+    fn test_gf2_matrix_mul() {
+        let a = matrix(&["110", "011"]);
+        let b = matrix(&["10", "11", "01"]);
+
+        assert_eq!(a.mul(&b), matrix(&["01", "10"]));
+        assert_eq!(a.transpose(), matrix(&["10", "11", "01"]));
+        assert_eq!(a.mul(&Gf2Matrix::identity(3)), a);
+        assert_eq!(
+            a.mul_vector(&Gf2Vector::from_bits(&[true, true, true])),
+            Gf2Vector::from_bits(&[false, false])
+        );
+    }
+
+    #[test]
+    fn test_gf2_rank_and_kernel() {
+        let m = matrix(&["1100", "0110", "1010"]);
+
+        assert_eq!(m.rank(), 2);
+
+        let kernel = m.kernel();
+        assert_eq!(kernel.len(), 2);
+        assert!(kernel.iter().all(|x| m.mul_vector(x).is_zero()));
+        assert_eq!(Gf2Matrix::from_columns(4, &kernel).rank(), 2);
+    }
+
+    #[test]
+    fn test_gf2_random_kernel() {
+        let rows = (0..100).map(|_| Gf2Vector::random(150)).collect::<Vec<_>>();
+        let m = Gf2Matrix::from_rows(150, rows);
+
+        let kernel = m.kernel();
+
+        assert_eq!(kernel.len(), 150 - m.rank());
+        assert!(kernel.iter().all(|x| m.mul_vector(x).is_zero()));
+    }
+
+    #[test]
+    fn test_gf2_solve() {
+        let m = matrix(&["1100", "0110", "1010"]);
+
+        let x = m
+            .solve(&Gf2Vector::from_bits(&[true, false, true]))
+            .unwrap();
+        assert_eq!(m.mul_vector(&x), Gf2Vector::from_bits(&[true, false, true]));
+        assert_eq!(m.solve(&Gf2Vector::from_bits(&[true, false, false])), None);
+
+        let rows = (0..200).map(|_| Gf2Vector::random(150)).collect::<Vec<_>>();
+        let m = Gf2Matrix::from_rows(150, rows);
+        let x = Gf2Vector::random(150);
+
+        assert_eq!(
+            m.mul_vector(&m.solve(&m.mul_vector(&x)).unwrap()),
+            m.mul_vector(&x)
+        );
+    }
+}
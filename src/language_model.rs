@@ -0,0 +1,227 @@
+//! Byte n-gram frequency tables of a language, trained from text corpora
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Reference English text, public domain prose
+pub const ENGLISH_CORPUS: &str = "corpus/english.txt";
+
+/// Unigram to quadgram counts of a language
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageModel {
+    pub language: String,
+    /// Counts of the n-grams of length n at index n - 1, hex encoded in the serialised form
+    #[serde(serialize_with = "serialize_tables")]
+    #[serde(deserialize_with = "deserialize_tables")]
+    tables: Vec<HashMap<Vec<u8>, u64>>,
+}
+
+impl LanguageModel {
+    pub const MAX_ORDER: usize = 4;
+
+    /// The model of the reference English corpus
+    pub fn english() -> Result<Self> {
+        let mut builder = LanguageModelBuilder::new("en");
+        builder.add_file(ENGLISH_CORPUS)?;
+
+        Ok(builder.build())
+    }
+
+    /// The English corpus reduced to small letters, for ciphers dropping word breaks and case
+    pub fn english_letters() -> Result<Self> {
+        let text = fs::read_to_string(ENGLISH_CORPUS)?
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>();
+
+        Ok(LanguageModelBuilder::new("en").add_text(&text).build())
+    }
+
+    pub fn max_order(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Counts of the n-grams of length `n`
+    pub fn counts(&self, n: usize) -> &HashMap<Vec<u8>, u64> {
+        assert!(
+            (1..=self.max_order()).contains(&n),
+            "The model has no {}-grams",
+            n
+        );
+
+        &self.tables[n - 1]
+    }
+
+    pub fn count(&self, ngram: &[u8]) -> u64 {
+        self.counts(ngram.len()).get(ngram).copied().unwrap_or(0)
+    }
+
+    /// Number of n-grams of length `n` seen in the corpus
+    pub fn total(&self, n: usize) -> u64 {
+        self.counts(n).values().sum()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(fs::write(path, self.to_json()?)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+fn serialize_tables<S: Serializer>(
+    tables: &[HashMap<Vec<u8>, u64>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    // Sorted keys keep the output stable
+    tables
+        .iter()
+        .map(|table| {
+            table
+                .iter()
+                .map(|(ngram, &count)| (hex::encode(ngram), count))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
+
+fn deserialize_tables<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<HashMap<Vec<u8>, u64>>, D::Error> {
+    Vec::<HashMap<String, u64>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|table| {
+            table
+                .into_iter()
+                .map(|(ngram, count)| Ok((hex::decode(ngram).map_err(D::Error::custom)?, count)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Accumulates the n-gram counts of any number of texts
+pub struct LanguageModelBuilder {
+    language: String,
+    tables: Vec<HashMap<Vec<u8>, u64>>,
+}
+
+impl LanguageModelBuilder {
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            tables: vec![HashMap::new(); LanguageModel::MAX_ORDER],
+        }
+    }
+
+    /// Counts the n-grams of `text`, none of them crossing its ends
+    pub fn add_bytes(&mut self, text: &[u8]) -> &mut Self {
+        for (n, table) in (1..).zip(self.tables.iter_mut()) {
+            for ngram in text.windows(n) {
+                *table.entry(ngram.to_vec()).or_insert(0) += 1;
+            }
+        }
+
+        self
+    }
+
+    pub fn add_text(&mut self, text: &str) -> &mut Self {
+        self.add_bytes(text.as_bytes())
+    }
+
+    /// Counts every line on its own, which suits word lists such as dictionary/american-english
+    pub fn add_lines(&mut self, text: &str) -> &mut Self {
+        for line in text.lines() {
+            self.add_text(line);
+        }
+
+        self
+    }
+
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        Ok(self.add_text(&fs::read_to_string(path)?))
+    }
+
+    /// Counts the plaintexts of a file of base64 lines, as data/19.txt and data/20.txt
+    pub fn add_base64_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        for line in fs::read_to_string(path)?.lines() {
+            self.add_bytes(&base64::decode(line.trim())?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn build(&self) -> LanguageModel {
+        LanguageModel {
+            language: self.language.clone(),
+            tables: self.tables.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_model_counts() {
+        let model = LanguageModelBuilder::new("en")
+            .add_text("abab")
+            .add_lines("ab\nba")
+            .build();
+
+        assert_eq!(model.max_order(), LanguageModel::MAX_ORDER);
+        assert_eq!(model.count(b"a"), 4);
+        assert_eq!(model.count(b"ab"), 3);
+        assert_eq!(model.count(b"ba"), 2);
+        assert_eq!(model.count(b"aba"), 1);
+        assert_eq!(model.count(b"abab"), 1);
+        assert_eq!(model.total(2), 5);
+        // Lines are counted on their own
+        assert_eq!(model.count(b"\n"), 0);
+        assert_eq!(model.count(b"bb"), 0);
+    }
+
+    #[test]
+    fn test_language_model_json() {
+        let model = LanguageModelBuilder::new("de")
+            .add_text("Grüße aus Köln")
+            .build();
+
+        let json = model.to_json().unwrap();
+
+        assert_eq!(LanguageModel::from_json(&json).unwrap(), model);
+    }
+
+    #[test]
+    fn test_language_model_english() {
+        let model = LanguageModel::english().unwrap();
+
+        assert!(model.count(b"e") > model.count(b"z"));
+        assert!(model.count(b"the ") > model.count(b"qu"));
+    }
+
+    #[test]
+    fn test_language_model_english_letters() {
+        let model = LanguageModel::english_letters().unwrap();
+
+        assert!(model.count(b"heli") > 0);
+        assert_eq!(model.count(b" "), 0);
+        assert_eq!(model.count(b"T"), 0);
+    }
+}
@@ -0,0 +1,240 @@
+//! Lattice basis reduction over exact rationals and the hidden number problem
+//! https://en.wikipedia.org/wiki/Lenstra%E2%80%93Lenstra%E2%80%93Lov%C3%A1sz_lattice_basis_reduction_algorithm
+
+use num_bigint::{BigInt, BigUint, ToBigInt};
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+pub type Vector = Vec<BigRational>;
+
+fn inner_product(a: &[BigRational], b: &[BigRational]) -> BigRational {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// The usual Lovász parameter 3/4 < delta < 1, larger is slower but gives shorter vectors
+pub fn default_delta() -> BigRational {
+    BigRational::new(BigInt::from(99), BigInt::from(100))
+}
+
+/// Gram-Schmidt coefficients mu[i][j] = <b_i, b*_j> / <b*_j, b*_j> and squared norms B[i] = <b*_i, b*_i>
+fn gram_schmidt(basis: &[Vector]) -> (Vec<Vec<BigRational>>, Vec<BigRational>) {
+    let n = basis.len();
+    let mut orthogonal: Vec<Vector> = Vec::with_capacity(n);
+    let mut mu = vec![vec![BigRational::zero(); n]; n];
+    let mut norms = Vec::with_capacity(n);
+
+    for i in 0..n {
+        let mut b_star = basis[i].clone();
+        for j in 0..i {
+            mu[i][j] = inner_product(&basis[i], &orthogonal[j]) / &norms[j];
+            for (x, y) in b_star.iter_mut().zip(orthogonal[j].iter()) {
+                *x -= &mu[i][j] * y;
+            }
+        }
+
+        norms.push(inner_product(&b_star, &b_star));
+        orthogonal.push(b_star);
+    }
+
+    (mu, norms)
+}
+
+/// LLL reduction of a basis of linearly independent rows, keeping the Gram-Schmidt data up to date
+/// incrementally instead of recomputing it after every step (Cohen, algorithm 2.6.3)
+pub fn lll(basis: &[Vector], delta: &BigRational) -> Vec<Vector> {
+    let n = basis.len();
+    let mut basis = basis.to_vec();
+    if n < 2 {
+        return basis;
+    }
+
+    let (mut mu, mut norms) = gram_schmidt(&basis);
+    assert!(
+        norms.iter().all(|norm| !norm.is_zero()),
+        "Basis vectors are not linearly independent"
+    );
+
+    let half = BigRational::new(BigInt::one(), BigInt::from(2));
+    let mut k = 1;
+    while k < n {
+        // Size reduction of b_k against b_(k-1), ..., b_0
+        for j in (0..k).rev() {
+            if mu[k][j].abs() > half {
+                let q = mu[k][j].round();
+
+                let (head, tail) = basis.split_at_mut(k);
+                for (x, y) in tail[0].iter_mut().zip(head[j].iter()) {
+                    *x -= &q * y;
+                }
+
+                let (head, tail) = mu.split_at_mut(k);
+                tail[0][j] -= &q;
+                for (x, y) in tail[0][..j].iter_mut().zip(head[j].iter()) {
+                    *x -= &q * y;
+                }
+            }
+        }
+
+        // Lovász condition
+        let mu_k = mu[k][k - 1].clone();
+        if norms[k] >= (delta - &mu_k * &mu_k) * &norms[k - 1] {
+            k += 1;
+            continue;
+        }
+
+        basis.swap(k, k - 1);
+
+        let new_norm = &norms[k] + &mu_k * &mu_k * &norms[k - 1];
+        mu[k][k - 1] = &mu_k * &norms[k - 1] / &new_norm;
+        norms[k] = &norms[k - 1] * &norms[k] / &new_norm;
+        norms[k - 1] = new_norm;
+
+        for j in 0..k - 1 {
+            let (head, tail) = mu.split_at_mut(k);
+            std::mem::swap(&mut head[k - 1][j], &mut tail[0][j]);
+        }
+        for i in k + 1..n {
+            let t = mu[i][k].clone();
+            mu[i][k] = &mu[i][k - 1] - &mu_k * &t;
+            mu[i][k - 1] = t + &mu[k][k - 1] * &mu[i][k];
+        }
+
+        k = (k - 1).max(1);
+    }
+
+    basis
+}
+
+/// Hidden number problem: recovers d modulo q from samples (t_i, u_i) satisfying
+/// |d t_i - u_i (mod q)| < q / 2^`known_bits`, by finding a short vector in the lattice spanned by
+///
+/// ```text
+/// q   0   ... 0    0    0
+/// 0   q   ... 0    0    0
+/// ...
+/// t_1 t_2 ... t_n  ct   0
+/// u_1 u_2 ... u_n  0    cu
+/// ```
+///
+/// with ct = 1 / 2^l and cu = q / 2^l. Returns every candidate found, which the caller has to check.
+pub fn solve_hidden_number_problem(
+    samples: &[(BigUint, BigUint)],
+    q: &BigUint,
+    known_bits: u32,
+) -> Vec<BigUint> {
+    let n = samples.len();
+    let q_int = q.to_bigint().unwrap();
+    let scale = BigInt::one() << known_bits;
+    let integer = |x: &BigUint| BigRational::from_integer(x.to_bigint().unwrap());
+
+    let ct = BigRational::new(BigInt::one(), scale.clone());
+    let cu = BigRational::new(q_int.clone(), scale.clone());
+
+    let mut basis: Vec<Vector> = (0..n)
+        .map(|i| {
+            let mut row = vec![BigRational::zero(); n + 2];
+            row[i] = integer(q);
+            row
+        })
+        .collect();
+
+    let mut t_row = samples.iter().map(|(t, _)| integer(t)).collect::<Vector>();
+    t_row.extend([ct, BigRational::zero()]);
+    let mut u_row = samples.iter().map(|(_, u)| integer(u)).collect::<Vector>();
+    u_row.extend([BigRational::zero(), cu.clone()]);
+    basis.push(t_row);
+    basis.push(u_row);
+
+    // The target vector d * t_row - u_row (+ multiples of q) has last entry -cu and second to last d / 2^l
+    lll(&basis, &default_delta())
+        .into_iter()
+        .filter(|row| row[n + 1].abs() == cu)
+        .filter_map(|row| {
+            let d = (&row[n] * BigRational::from_integer(scale.clone())).to_integer();
+            let d = if row[n + 1] == cu { -d } else { d };
+
+            d.mod_floor(&q_int).to_biguint()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number_theory::random_in_range;
+
+    fn integer_basis(rows: &[&[i64]]) -> Vec<Vector> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&x| BigRational::from_integer(BigInt::from(x)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lll() {
+        let basis = integer_basis(&[&[1, 1, 1], &[-1, 0, 2], &[3, 5, 6]]);
+
+        let reduced = lll(&basis, &BigRational::new(BigInt::from(3), BigInt::from(4)));
+
+        assert_eq!(
+            reduced,
+            integer_basis(&[&[0, 1, 0], &[1, 0, 1], &[-1, 0, 2]])
+        );
+    }
+
+    #[test]
+    fn test_lll_rational() {
+        // Example from challenge 62, entries are given in halves
+        let halves = |rows: &[&[i64]]| -> Vec<Vector> {
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&x| BigRational::new(BigInt::from(x), BigInt::from(2)))
+                        .collect()
+                })
+                .collect()
+        };
+        let basis = halves(&[
+            &[-4, 0, 4, 0],
+            &[1, -2, 0, 0],
+            &[-2, 0, -4, 1],
+            &[-2, 2, 2, 4],
+        ]);
+
+        let reduced = lll(&basis, &default_delta());
+
+        assert_eq!(
+            reduced,
+            halves(&[
+                &[1, -2, 0, 0],
+                &[-2, 0, -4, 1],
+                &[-1, 0, 2, 4],
+                &[-3, -2, 4, 0]
+            ])
+        );
+    }
+
+    #[test]
+    fn test_hidden_number_problem() {
+        let q = BigUint::parse_bytes(b"29246302889428143187362802287225875743", 10).unwrap();
+        let known_bits = 48;
+        let d = random_in_range(&BigUint::one(), &q);
+
+        // u = d t - e (mod q) with small e
+        let bound = &q >> known_bits;
+        let samples = (0..4)
+            .map(|_| {
+                let t = random_in_range(&BigUint::one(), &q);
+                let e = random_in_range(&BigUint::zero(), &bound);
+                let u = (&d * &t + &q - e) % &q;
+                (t, u)
+            })
+            .collect::<Vec<_>>();
+
+        assert!(solve_hidden_number_problem(&samples, &q, known_bits).contains(&d));
+    }
+}
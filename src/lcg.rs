@@ -0,0 +1,308 @@
+//! Generators built on linear congruential generators: glibc rand() and java.util.Random
+//! https://en.wikipedia.org/wiki/Linear_congruential_generator
+
+use rand_core::{impls, Error, RngCore, SeedableRng};
+
+use crate::gf2::{Gf2Matrix, Gf2Vector};
+
+/// Default glibc rand() (TYPE_3): an additive feedback generator r_i = r_(i-3) + r_(i-31) whose
+/// state is seeded with the Park-Miller LCG, every output dropping the lowest bit of r_i
+/// https://www.mathstat.dal.ca/~selinger/random/
+pub struct GlibcRand {
+    r: [u32; GlibcRand::DEGREE],
+    /// Position of r_(i-31)
+    index: usize,
+}
+
+impl GlibcRand {
+    const DEGREE: usize = 31;
+    const SEPARATION: usize = 3;
+    const DISCARDED: usize = 310;
+
+    /// Same as srand(seed)
+    pub fn new(seed: u32) -> Self {
+        let mut r = [0_u32; 34];
+        r[0] = if seed == 0 { 1 } else { seed };
+        for i in 1..Self::DEGREE {
+            let word = (16807 * r[i - 1] as i32 as i64) % 2_147_483_647;
+            r[i] = if word < 0 { word + 2_147_483_647 } else { word } as u32;
+        }
+        for i in Self::DEGREE..34 {
+            r[i] = r[i - Self::DEGREE];
+        }
+
+        let mut state = [0; Self::DEGREE];
+        state.copy_from_slice(&r[34 - Self::DEGREE..]);
+        let mut rng = Self { r: state, index: 0 };
+        (0..Self::DISCARDED).for_each(|_| {
+            rng.next_word();
+        });
+
+        rng
+    }
+
+    fn next_word(&mut self) -> u32 {
+        let lag = (self.index + Self::DEGREE - Self::SEPARATION) % Self::DEGREE;
+        let word = self.r[self.index].wrapping_add(self.r[lag]);
+        self.r[self.index] = word;
+        self.index = (self.index + 1) % Self::DEGREE;
+
+        word
+    }
+
+    /// Same as rand(), in [0, 2^31)
+    pub fn next_rand(&mut self) -> u32 {
+        self.next_word() >> 1
+    }
+
+    /// Clones a generator from consecutive outputs, predicting the ones following them.
+    ///
+    /// Every output o_i is o_(i-31) + o_(i-3) + c_i, where the carry c_i is set exactly when the
+    /// dropped bits b_(i-31) and b_(i-3) both are. As b_i = b_(i-31) ^ b_(i-3), every carry gives
+    /// two linear equations on the 31 dropped bits of the first outputs. A few hundred outputs are
+    /// usually enough, and None is returned until the equations pin every bit down.
+    pub fn clone_from_outputs(outputs: &[u32]) -> Option<Self> {
+        let degree = Self::DEGREE;
+        if outputs.len() < degree {
+            return None;
+        }
+
+        // The dropped bit of every output as a combination of the dropped bits of the first ones
+        let mut dropped_bits = (0..degree)
+            .map(|i| Gf2Vector::from_bits(&(0..degree).map(|j| i == j).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        let mut equations = Gf2Matrix::zero(0, degree);
+
+        for i in degree..outputs.len() {
+            let (older, lag) = (i - degree, i - Self::SEPARATION);
+            let carry = outputs[i]
+                .wrapping_sub(outputs[older])
+                .wrapping_sub(outputs[lag])
+                & 0x7FFF_FFFF;
+            match carry {
+                0 => {}
+                1 => {
+                    equations.push_row(dropped_bits[older].clone());
+                    equations.push_row(dropped_bits[lag].clone());
+                }
+                _ => return None,
+            }
+
+            let mut bit = dropped_bits[older].clone();
+            bit.xor_assign(&dropped_bits[lag]);
+            dropped_bits.push(bit);
+        }
+
+        // Without full rank several sets of dropped bits fit the outputs seen so far
+        if equations.rank() < degree {
+            return None;
+        }
+
+        let ones = Gf2Vector::from_bits(&vec![true; equations.num_rows()]);
+        let first_bits = equations.solve(&ones)?;
+
+        let mut rng = Self {
+            r: [0; Self::DEGREE],
+            index: 0,
+        };
+        for (word, (&output, bit)) in rng.r.iter_mut().zip(outputs.iter().zip(&dropped_bits)) {
+            *word = output << 1 | bit.dot(&first_bits) as u32;
+        }
+
+        // Replays the outputs, which catches outputs not coming from the generator
+        outputs[degree..]
+            .iter()
+            .all(|&output| rng.next_rand() == output)
+            .then(|| rng)
+    }
+}
+
+impl RngCore for GlibcRand {
+    /// Outputs of rand(), so the top bit is always zero
+    fn next_u32(&mut self) -> u32 {
+        self.next_rand()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for GlibcRand {
+    type Seed = [u8; 4];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u32::from_le_bytes(seed))
+    }
+
+    /// Same as srand(), instead of expanding the seed with PCG32
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed as u32)
+    }
+}
+
+/// java.util.Random, a 48 bit LCG giving out its top bits
+pub struct JavaRandom {
+    seed: u64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: u64 = 0x5_DEEC_E66D;
+    const ADDEND: u64 = 0xB;
+    const MASK: u64 = (1 << 48) - 1;
+
+    /// Same as new Random(seed)
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed: (seed as u64 ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> u32 {
+        self.seed = (self
+            .seed
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::ADDEND))
+            & Self::MASK;
+
+        (self.seed >> (48 - bits)) as u32
+    }
+
+    /// Same as nextInt()
+    pub fn next_int(&mut self) -> i32 {
+        self.next(32) as i32
+    }
+
+    /// Same as nextLong()
+    pub fn next_long(&mut self) -> i64 {
+        let high = self.next_int() as i64;
+        let low = self.next_int() as i64;
+
+        (high << 32).wrapping_add(low)
+    }
+
+    /// Clones a generator from consecutive outputs of nextInt(), predicting the ones following
+    /// them. Every output is the top 32 bits of the state, so only the 16 low bits need to be
+    /// guessed.
+    pub fn clone_from_outputs(outputs: &[i32]) -> Option<Self> {
+        if outputs.len() < 2 {
+            return None;
+        }
+
+        (0..1 << 16).find_map(|low| {
+            let mut rng = Self {
+                seed: (outputs[0] as u32 as u64) << 16 | low,
+            };
+
+            outputs[1..]
+                .iter()
+                .all(|&output| rng.next_int() == output)
+                .then(|| rng)
+        })
+    }
+}
+
+impl RngCore for JavaRandom {
+    fn next_u32(&mut self) -> u32 {
+        self.next(32)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next_long() as u64
+    }
+
+    /// Same as nextBytes(), every int giving out 4 bytes starting from its low ones
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next(32).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for JavaRandom {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(i64::from_le_bytes(seed))
+    }
+
+    /// Same as new Random(seed), instead of expanding the seed with PCG32
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_glibc_rand() {
+        // Output of srand(1) followed by rand()
+        let mut rng = GlibcRand::new(1);
+        let outputs = (0..5).map(|_| rng.next_rand()).collect::<Vec<_>>();
+
+        assert_eq!(
+            outputs,
+            [1804289383, 846930886, 1681692777, 1714636915, 1957747793]
+        );
+    }
+
+    #[test]
+    fn test_glibc_rand_clone() {
+        let mut rng = GlibcRand::new(thread_rng().gen());
+        let outputs = (0..300).map(|_| rng.next_rand()).collect::<Vec<_>>();
+
+        let mut cloned = GlibcRand::clone_from_outputs(&outputs).unwrap();
+
+        for _ in 0..1000 {
+            assert_eq!(cloned.next_rand(), rng.next_rand());
+        }
+    }
+
+    #[test]
+    fn test_glibc_rand_clone_short() {
+        let mut rng = GlibcRand::new(thread_rng().gen());
+        let outputs = (0..40).map(|_| rng.next_rand()).collect::<Vec<_>>();
+
+        // Nine outputs past the first 31 give at most 18 equations on 31 bits
+        assert!(GlibcRand::clone_from_outputs(&outputs).is_none());
+    }
+
+    #[test]
+    fn test_java_random() {
+        // Output of new Random(42).nextInt()
+        let mut rng = JavaRandom::new(42);
+
+        assert_eq!(rng.next_int(), -1170105035);
+        assert_eq!(rng.next_int(), 234785527);
+    }
+
+    #[test]
+    fn test_java_random_clone() {
+        let mut rng = JavaRandom::new(thread_rng().gen());
+        let outputs = (0..3).map(|_| rng.next_int()).collect::<Vec<_>>();
+
+        let mut cloned = JavaRandom::clone_from_outputs(&outputs).unwrap();
+
+        for _ in 0..100 {
+            assert_eq!(cloned.next_u64(), rng.next_u64());
+        }
+    }
+}
@@ -2,9 +2,28 @@
 
 mod aes;
 mod challenges;
+mod classical;
+mod clock;
+mod crib_drag;
+mod ctr_breaker;
 mod datatypes;
 mod detect;
+mod diffie_hellman;
+mod dlog;
+mod elliptic_curve;
+mod gf128;
+mod gf2;
+mod language_model;
+mod lattice;
+mod lcg;
+mod mac;
+mod number_theory;
 mod oracle;
 mod padding;
+mod pcg;
+mod plaintext;
 mod mersenne_twister;
+mod rc4;
+mod rsa;
 mod utils;
+mod xorshift;
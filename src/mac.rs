@@ -0,0 +1,41 @@
+//! Message authentication and key derivation used by the key exchange protocols
+
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+
+use crate::aes::AES_BLOCK_SIZE;
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let key = PKey::hmac(key).expect("Invalid HMAC key");
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+    signer.update(message).unwrap();
+
+    signer.sign_to_vec().unwrap()
+}
+
+/// Derives a symmetric key from a shared secret as the first AES block of SHA-256
+pub fn derive_key(shared_secret: &[u8]) -> Vec<u8> {
+    sha256(shared_secret)[..AES_BLOCK_SIZE].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_derive_key() {
+        assert_eq!(derive_key(b"secret").len(), AES_BLOCK_SIZE);
+        assert_ne!(derive_key(b"secret"), derive_key(b"secreT"));
+    }
+}
@@ -1,135 +1,486 @@
-//! Implementation of MT19937 algorithm
+//! Implementation of the MT19937 and MT19937-64 algorithms
 //! https://en.wikipedia.org/wiki/Mersenne_Twister#Pseudocode
 
-use rand_core::SeedableRng;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
 
-pub(crate) const DEFAULT_SEED: u64 = 5489;
+use rand_core::{impls, Error, RngCore, SeedableRng};
+use threadpool::ThreadPool;
 
-// Constants for MT19937-64
-pub(crate) const W: u64 = 64;
-pub(crate) const N: usize = 312;
-pub(crate) const M: usize = 156;
-pub(crate) const R: u64 = 31;
-pub(crate) const A: u64 = 0xB502_6F5A_A966_19E9;
-pub(crate) const U: u64 = 29;
-pub(crate) const D: u64 = 0x5555_5555_5555_5555;
-pub(crate) const S: u64 = 17;
-pub(crate) const B: u64 = 0x71D6_7FFF_EDA6_0000;
-pub(crate) const T: u64 = 37;
-pub(crate) const C: u64 = 0xFFF7_EEE0_0000_0000;
-pub(crate) const L: u64 = 43;
-pub(crate) const F: u128 = 6364136223846793005;
-
-const LOWEST_W_MASK: u128 = 0xFFFF_FFFF_FFFF_FFFF;
-const LOWER_MASK: u64 = (1 << R) - 1; // The binary number of r 1's
-const UPPER_MASK: u64 = ((!LOWER_MASK as u128) & LOWEST_W_MASK) as u64; // lowest W bits of LOWER_MASK
-
-pub struct MT19937_64
-{
-    mt: [u64; N],
-    index: usize,
-}
+use crate::gf2::{Gf2Matrix, Gf2Vector};
 
-impl MT19937_64 {
-    pub fn new() -> Self {
-        Self::new_with_state([0; N])
-    }
+pub(crate) const DEFAULT_SEED: u64 = 5489;
 
-    pub fn new_with_state(mt: [u64; N]) -> Self {
-        Self {
-            mt,
-            index: N+1,
+/// Both variants share the algorithm and only differ in word size and constants
+macro_rules! mersenne_twister {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $word:ty, $next:ident,
+        w: $w:expr, n: $n:expr, m: $m:expr, r: $r:expr, a: $a:expr,
+        u: $u:expr, d: $d:expr, s: $s:expr, b: $b:expr, t: $t:expr, c: $c:expr, l: $l:expr, f: $f:expr $(,)?
+    ) => {
+        $(#[$attr])*
+        pub struct $name {
+            mt: [$word; $n],
+            index: usize,
         }
-    }
 
-    pub fn from_seed(seed: u64) -> Self {
-        let mut mt = Self::new();
-        mt.seed_mt(seed);
+        impl $name {
+            pub const W: u32 = $w;
+            pub const N: usize = $n;
+            pub const M: usize = $m;
+            pub const R: u32 = $r;
+            pub const A: $word = $a;
+            pub const U: u32 = $u;
+            pub const D: $word = $d;
+            pub const S: u32 = $s;
+            pub const B: $word = $b;
+            pub const T: u32 = $t;
+            pub const C: $word = $c;
+            pub const L: u32 = $l;
+            pub const F: $word = $f;
 
-        mt
-    }
+            /// The binary number of r 1's
+            const LOWER_MASK: $word = (1 << $r) - 1;
+            const UPPER_MASK: $word = !Self::LOWER_MASK;
 
-    fn twist(&mut self) {
-        for i in 0..N-1 {
-            let x = (self.mt[i] & UPPER_MASK)
-                | (self.mt[(i + 1) % N] & LOWER_MASK);
+            pub fn new() -> Self {
+                Self::from_seed(DEFAULT_SEED as $word)
+            }
 
-            let mut x_a = x >> 1;
+            /// Generator whose state array is `mt`, the next output being the first one of the
+            /// following twist
+            pub fn new_with_state(mt: [$word; $n]) -> Self {
+                Self { mt, index: $n }
+            }
 
-            if x % 2 != 0 {
-                x_a ^= A;
+            pub fn from_seed(seed: $word) -> Self {
+                let mut mt = Self::new_with_state([0; $n]);
+                mt.seed_mt(seed);
+
+                mt
             }
-            self.mt[i] = self.mt[(i + M) % N] ^ x_a;
-        }
 
-        self.index = 0;
-    }
+            /// Clones a generator from N consecutive outputs, taken from the start of `outputs`.
+            /// The clone predicts the outputs following them.
+            pub fn clone_from_outputs<I: IntoIterator<Item = $word>>(outputs: I) -> Option<Self> {
+                let mut mt = [0; $n];
+                let mut count = 0;
+                for (word, output) in mt.iter_mut().zip(outputs) {
+                    *word = Self::untemper(output);
+                    count += 1;
+                }
+
+                (count == $n).then(|| Self::new_with_state(mt))
+            }
+
+            /// Clones a generator from consecutive outputs of which only the bits in `mask` are
+            /// known, such as the top half or the top byte. Twisting and tempering are linear over
+            /// GF(2), so every known bit is a linear equation on the N * W bits of the state
+            /// preceding the outputs. Narrow masks need many more than N * W / `mask.count_ones()`
+            /// outputs, since every twist only moves the state bits by one position. The clone
+            /// predicts the outputs following them.
+            pub fn clone_from_masked_outputs(outputs: &[$word], mask: $word) -> Option<Self> {
+                let known_bits = move |output: $word| {
+                    (0..$w)
+                        .filter(move |bit| (mask >> bit) & 1 == 1)
+                        .map(move |bit| (output >> bit) & 1 == 1)
+                };
+
+                // The known bits produced from every single state bit give one column of the system
+                let columns = (0..$n * $w)
+                    .map(|i| {
+                        let mut state = [0; $n];
+                        state[i / $w] = 1 << (i % $w);
+                        let mut mt = Self::new_with_state(state);
 
-    fn seed_mt(&mut self, seed: u64) {
-        self.index = N;
-        self.mt[0] = seed;
+                        let bits = outputs
+                            .iter()
+                            .flat_map(|_| known_bits(mt.extract_number()))
+                            .collect::<Vec<_>>();
+                        Gf2Vector::from_bits(&bits)
+                    })
+                    .collect::<Vec<_>>();
+                let known = outputs
+                    .iter()
+                    .flat_map(|&output| known_bits(output))
+                    .collect::<Vec<_>>();
+
+                let system = Gf2Matrix::from_columns(known.len(), &columns);
+                let solution = system.solve(&Gf2Vector::from_bits(&known))?;
+
+                let mut state = [0; $n];
+                for i in solution.ones() {
+                    state[i / $w] |= 1 << (i % $w);
+                }
+                let mut mt = Self::new_with_state(state);
+                outputs.iter().for_each(|_| {
+                    mt.extract_number();
+                });
+
+                Some(mt)
+            }
+
+            /// New value of the state word `current`, given the word after it and the one M ahead
+            fn twist_word(current: $word, next: $word, ahead: $word) -> $word {
+                let x = (current & Self::UPPER_MASK) | (next & Self::LOWER_MASK);
+
+                let mut x_a = x >> 1;
+                if x % 2 != 0 {
+                    x_a ^= Self::A;
+                }
+                ahead ^ x_a
+            }
+
+            fn twist(&mut self) {
+                for i in 0..$n {
+                    let (next, ahead) = (self.mt[(i + 1) % $n], self.mt[(i + $m) % $n]);
+                    self.mt[i] = Self::twist_word(self.mt[i], next, ahead);
+                }
+
+                self.index = 0;
+            }
+
+            /// State word i when seeding, given word i - 1
+            fn seed_word(previous: $word, i: usize) -> $word {
+                Self::F
+                    .wrapping_mul(previous ^ (previous >> ($w - 2)))
+                    .wrapping_add(i as $word)
+            }
+
+            fn seed_mt(&mut self, seed: $word) {
+                self.index = $n;
+                self.mt[0] = seed;
+
+                for i in 1..$n {
+                    self.mt[i] = Self::seed_word(self.mt[i - 1], i);
+                }
+            }
 
-        for i in 1..N-1 {
-            self.mt[i] = (LOWEST_W_MASK &
-                (
-                    (F * (self.mt[i-1] ^ (self.mt[i-1] >> (W - 2))) as u128) + (i as u128)
-                )) as u64;
+            /// Output number `position` (from 0) of the generator seeded with `seed`. Outputs of
+            /// the first twist before N - M only depend on the seeded words up to M after them, so
+            /// only those are computed.
+            pub fn output_at(seed: $word, position: usize) -> $word {
+                if position + $m >= $n {
+                    let mut mt = Self::from_seed(seed);
+                    (0..position).for_each(|_| {
+                        mt.extract_number();
+                    });
+                    return mt.extract_number();
+                }
+
+                let (mut current, mut next) = (seed, seed);
+                let mut word = seed;
+                for i in 1..=position + $m {
+                    word = Self::seed_word(word, i);
+                    if i == position {
+                        current = word;
+                    }
+                    if i == position + 1 {
+                        next = word;
+                    }
+                }
+
+                Self::temper(Self::twist_word(current, next, word))
+            }
+
+            /// Searches `seeds` for one giving `output` at `position`, splitting the range over a
+            /// pool of `threads` threads which stop as soon as any finds it.
+            pub fn search_seed(
+                output: $word,
+                position: usize,
+                seeds: RangeInclusive<$word>,
+                threads: usize,
+            ) -> Option<$word> {
+                assert!(threads > 0, "At least one thread is needed");
+                if seeds.is_empty() {
+                    return None;
+                }
+
+                let (tx, rx) = channel();
+                let pool = ThreadPool::new(threads);
+                let found = Arc::new(AtomicBool::new(false));
+
+                let (start, end) = seeds.into_inner();
+                let chunk = ((end - start) / threads as $word).saturating_add(1);
+                let jobs = (0..threads as $word)
+                    .map_while(|i| {
+                        let first = start.checked_add(i.checked_mul(chunk)?)?;
+                        let last = if i + 1 == threads as $word {
+                            end
+                        } else {
+                            first.saturating_add(chunk - 1).min(end)
+                        };
+                        (first <= end).then(|| (first, last))
+                    })
+                    .collect::<Vec<_>>();
+
+                for &(first, last) in jobs.iter() {
+                    let tx = tx.clone();
+                    let found = found.clone();
+
+                    pool.execute(move || {
+                        // Checking the flag for every seed would cost more than the search
+                        let seed = (first..=last)
+                            .take_while(|&seed| seed % 4096 != 0 || !found.load(Ordering::Relaxed))
+                            .find(|&seed| Self::output_at(seed, position) == output);
+                        if seed.is_some() {
+                            found.store(true, Ordering::Relaxed);
+                        }
+
+                        tx.send(seed).expect("Unable to send seed");
+                    });
+                }
+
+                rx.iter().take(jobs.len()).flatten().min()
+            }
+
+            fn extract_number(&mut self) -> $word {
+                if self.index >= $n {
+                    self.twist();
+                }
+
+                let y = Self::temper(self.mt[self.index]);
+                self.index += 1;
+
+                y
+            }
+
+            pub fn $next(&mut self) -> $word {
+                self.extract_number()
+            }
+
+            pub fn temper(y: $word) -> $word {
+                let mut y = y;
+                y ^= (y >> Self::U) & Self::D;
+                y ^= (y << Self::S) & Self::B;
+                y ^= (y << Self::T) & Self::C;
+                y ^= y >> Self::L;
+
+                y
+            }
+
+            /// Inverse of `temper`, undoing its steps in reverse order
+            pub fn untemper(y: $word) -> $word {
+                let y = Self::inv_right_shift_xor(y, Self::L, !0);
+                let y = Self::inv_left_shift_xor(y, Self::T, Self::C);
+                let y = Self::inv_left_shift_xor(y, Self::S, Self::B);
+
+                Self::inv_right_shift_xor(y, Self::U, Self::D)
+            }
+
+            /// Inverse of y ^= (y >> k) & mask. Every iteration recovers k more of the high bits.
+            fn inv_right_shift_xor(u: $word, k: u32, mask: $word) -> $word {
+                assert!(k >= 1);
+                let mut v = u;
+
+                for _ in 0..$w / k {
+                    v = u ^ ((v >> k) & mask);
+                }
+                v
+            }
+
+            /// Inverse of y ^= (y << k) & mask. Every iteration recovers k more of the low bits.
+            fn inv_left_shift_xor(u: $word, k: u32, mask: $word) -> $word {
+                assert!(k >= 1);
+                let mut v = u;
+
+                for _ in 0..$w / k {
+                    v = u ^ ((v << k) & mask);
+                }
+                v
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
         }
-    }
 
-    fn extract_number(&mut self) -> u64 {
-        debug_assert!(self.index != 0);
+        impl SeedableRng for $name {
+            type Seed = [u8; std::mem::size_of::<$word>()];
 
-        if self.index >= N {
-            if self.index > N {
-                self.seed_mt(DEFAULT_SEED);
+            fn from_seed(seed: Self::Seed) -> Self {
+                Self::from_seed(<$word>::from_le_bytes(seed))
             }
 
-            self.twist();
+            /// Same as seeding the reference implementation, instead of expanding the seed with
+            /// PCG32
+            fn seed_from_u64(seed: u64) -> Self {
+                Self::from_seed(seed as $word)
+            }
         }
+    };
+}
 
-        let mut y: u64 = self.mt[self.index];
-        y ^= (y >> U) & D;
-        y ^= (y << S) & B;
-        y ^= (y << T) & C;
-        y ^= y >> L;
+mersenne_twister!(
+    /// 32 bit MT19937, as std::mt19937 in C++
+    #[allow(non_camel_case_types)]
+    MT19937, u32, next_u32,
+    w: 32, n: 624, m: 397, r: 31, a: 0x9908_B0DF,
+    u: 11, d: 0xFFFF_FFFF, s: 7, b: 0x9D2C_5680, t: 15, c: 0xEFC6_0000, l: 18, f: 1_812_433_253,
+);
 
-        self.index += 1;
+mersenne_twister!(
+    /// 64 bit MT19937-64, as std::mt19937_64 in C++
+    #[allow(non_camel_case_types)]
+    MT19937_64, u64, next_u64,
+    w: 64, n: 312, m: 156, r: 31, a: 0xB502_6F5A_A966_19E9,
+    u: 29, d: 0x5555_5555_5555_5555, s: 17, b: 0x71D6_7FFF_EDA6_0000, t: 37, c: 0xFFF7_EEE0_0000_0000, l: 43,
+    f: 6_364_136_223_846_793_005,
+);
 
-        y // & LOWEST_W_MASK
+impl RngCore for MT19937 {
+    fn next_u32(&mut self) -> u32 {
+        self.extract_number()
     }
 
-    pub fn next_u64(&mut self) -> u64 {
-        self.extract_number()
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
     }
-}
 
-impl SeedableRng for MT19937_64 {
-    type Seed = [u8; 8];
-    fn from_seed(seed: Self::Seed) -> Self {
-        Self::from_seed(u64::from_le_bytes(seed))
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
+impl RngCore for MT19937_64 {
+    /// The low half of the next output
+    fn next_u32(&mut self) -> u32 {
+        self.extract_number() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.extract_number()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_mt19937() {
+        let mut rng = MT19937::from_seed(DEFAULT_SEED as u32);
+
+        // values are taken from C++ std::mt19937 in <random>
+        const CPP_NUMS: [u32; 10] = [
+            3499211612, 581869302, 3890346734, 3586334585, 545404204, 4161255391, 3922919429, 949333985,
+            2715962298, 1323567403,
+        ];
+
+        CPP_NUMS.iter().for_each(|&i| {
+            assert_eq!(rng.next_u32(), i);
+        });
+    }
+
     #[test]
     fn test_mt19937_64() {
         let seed = DEFAULT_SEED;
         let mut rng = MT19937_64::from_seed(seed);
 
         // values are taked from C++ std::mt19937_64 in <random>
-        const CPP_NUMS: [u64; 20] = [14514284786278117030, 4620546740167642908, 13109570281517897720, 17462938647148434322,
+        const CPP_NUMS: [u64; 20] = [
+            14514284786278117030, 4620546740167642908, 13109570281517897720, 17462938647148434322,
             355488278567739596, 7469126240319926998, 4635995468481642529, 418970542659199878, 9604170989252516556,
-            6358044926049913402, 5058016125798318033, 10349215569089701407, 2583272014892537200, 10032373690199166667,
-            9627645531742285868, 15810285301089087632, 9219209713614924562, 7736011505917826031, 13729552270962724157,
-            4596340717661012313];
+            6358044926049913402, 5058016125798318033, 10349215569089701407, 2583272014892537200,
+            10032373690199166667, 9627645531742285868, 15810285301089087632, 9219209713614924562,
+            7736011505917826031, 13729552270962724157, 4596340717661012313,
+        ];
 
         CPP_NUMS.iter().for_each(|&i| {
             assert_eq!(rng.next_u64(), i);
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mt19937_10000th_output() {
+        // The C++ standard requires these values for the 10000th invocation of a default constructed engine
+        let mut rng = MT19937::new();
+        assert_eq!((0..10000).map(|_| rng.next_u32()).last(), Some(4_123_659_995));
+
+        let mut rng = MT19937_64::new();
+        assert_eq!((0..10000).map(|_| rng.next_u64()).last(), Some(9_981_545_732_273_789_042));
+    }
+
+    #[test]
+    fn test_output_at() {
+        for position in [0, 1, 100, MT19937::N - MT19937::M - 1, MT19937::N - MT19937::M, 700] {
+            let mut rng = MT19937::from_seed(1234);
+            let expected = (0..=position).map(|_| rng.next_u32()).last().unwrap();
+            assert_eq!(MT19937::output_at(1234, position), expected);
+        }
+
+        for position in [0, MT19937_64::N - MT19937_64::M - 1, MT19937_64::N] {
+            let mut rng = MT19937_64::from_seed(1234);
+            let expected = (0..=position).map(|_| rng.next_u64()).last().unwrap();
+            assert_eq!(MT19937_64::output_at(1234, position), expected);
+        }
+    }
+
+    fn test_search_seed(range: u32) {
+        let start = rand::random::<u32>() / 2;
+        let seed = start + rand::random::<u32>() % range;
+        let output = MT19937::output_at(seed, 3);
+
+        let found = MT19937::search_seed(output, 3, start..=start + range - 1, 4);
+
+        assert_eq!(found, Some(seed));
+    }
+
+    #[test]
+    fn test_search_seed_small() {
+        test_search_seed(1 << 16);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_search_seed_large() {
+        test_search_seed(1 << 24);
+    }
+
+    #[test]
+    fn test_untemper() {
+        for y in [0, 1, u32::MAX, 3499211612] {
+            assert_eq!(MT19937::untemper(MT19937::temper(y)), y);
+            assert_eq!(MT19937::temper(MT19937::untemper(y)), y);
+        }
+        for y in [0, 1, u64::MAX, 14514284786278117030] {
+            assert_eq!(MT19937_64::untemper(MT19937_64::temper(y)), y);
+            assert_eq!(MT19937_64::temper(MT19937_64::untemper(y)), y);
+        }
+    }
+
+    #[test]
+    fn test_rng_core() {
+        let mut rng = MT19937_64::seed_from_u64(DEFAULT_SEED);
+        assert_eq!(RngCore::next_u64(&mut rng), 14514284786278117030);
+        assert_eq!(RngCore::next_u32(&mut rng), 4620546740167642908_u64 as u32);
+
+        // Bytes are taken little endian from every 64 bit output
+        let mut bytes = [0; 12];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..8], 13109570281517897720_u64.to_le_bytes());
+        assert_eq!(bytes[8..], 17462938647148434322_u64.to_le_bytes()[..4]);
+
+        let mut rng = MT19937::seed_from_u64(DEFAULT_SEED);
+        assert_eq!(RngCore::next_u64(&mut rng), (581869302 << 32) | 3499211612);
+    }
+}
@@ -0,0 +1,248 @@
+//! Modular arithmetic helpers shared by the public key attacks
+
+use num_bigint::{BigUint, RandBigInt, ToBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::thread_rng;
+
+/// Multiplicative inverse of `a` modulo `m`, if it exists
+pub fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let a = a.to_bigint().unwrap();
+    let m = m.to_bigint().unwrap();
+
+    let extended = a.extended_gcd(&m);
+    if !extended.gcd.is_one() {
+        return None;
+    }
+
+    extended.x.mod_floor(&m).to_biguint()
+}
+
+/// (a - b) mod m for a, b already reduced modulo m
+pub fn mod_sub(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    if a >= b {
+        a - b
+    } else {
+        m - (b - a)
+    }
+}
+
+/// Legendre symbol, true if `a` is a non-zero quadratic residue modulo the odd prime `p`
+pub fn is_quadratic_residue(a: &BigUint, p: &BigUint) -> bool {
+    let exponent = (p - 1_u32) >> 1;
+    a.modpow(&exponent, p).is_one()
+}
+
+/// Square root of `a` modulo the odd prime `p` by Tonelli-Shanks
+pub fn mod_sqrt(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let a = a % p;
+    if a.is_zero() {
+        return Some(a);
+    }
+    if !is_quadratic_residue(&a, p) {
+        return None;
+    }
+
+    // p - 1 = q * 2^s with q odd
+    let p_minus_one = p - 1_u32;
+    let s = p_minus_one.trailing_zeros().unwrap();
+    let q = &p_minus_one >> s;
+
+    // Find a quadratic non-residue
+    let mut z = BigUint::from(2_u32);
+    while is_quadratic_residue(&z, p) {
+        z += 1_u32;
+    }
+
+    let mut m = s;
+    let mut c = z.modpow(&q, p);
+    let mut t = a.modpow(&q, p);
+    let mut r = a.modpow(&((&q + 1_u32) >> 1), p);
+
+    while !t.is_one() {
+        // Least i with t^(2^i) = 1
+        let mut i = 0;
+        let mut t_squared = t.clone();
+        while !t_squared.is_one() {
+            t_squared = &t_squared * &t_squared % p;
+            i += 1;
+        }
+
+        let b = c.modpow(&(BigUint::one() << (m - i - 1)), p);
+        m = i;
+        c = &b * &b % p;
+        t = t * &c % p;
+        r = r * b % p;
+    }
+
+    Some(r)
+}
+
+/// Chinese remainder theorem for pairwise coprime moduli.
+/// Returns x and the product of the moduli, such that x = residue_i (mod modulus_i) for all i.
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> (BigUint, BigUint) {
+    let modulus: BigUint = congruences.iter().map(|(_, m)| m).product();
+
+    let x = congruences
+        .iter()
+        .fold(BigUint::zero(), |acc, (residue, m)| {
+            let n = &modulus / m;
+            let inverse = mod_inverse(&(&n % m), m).expect("Moduli are not coprime");
+
+            (acc + residue * n * inverse) % &modulus
+        });
+
+    (x, modulus)
+}
+
+/// Prime factors below `bound` found by trial division, as (prime, exponent).
+/// Composite divisors never divide, as their prime factors have already been divided out.
+pub fn small_prime_factors(n: &BigUint, bound: u64) -> Vec<(u64, u32)> {
+    let mut n = n.clone();
+    let mut factors = vec![];
+
+    for divisor in 2..bound {
+        let mut exponent = 0;
+        while (&n % divisor).is_zero() {
+            n /= divisor;
+            exponent += 1;
+        }
+
+        if exponent > 0 {
+            factors.push((divisor, exponent));
+        }
+    }
+
+    factors
+}
+
+/// Primes below `bound` by the sieve of Eratosthenes
+pub fn small_primes(bound: u64) -> Vec<u64> {
+    let mut is_composite = vec![false; bound as usize];
+
+    (2..bound)
+        .filter(|&n| {
+            if is_composite[n as usize] {
+                return false;
+            }
+            (n * n..bound).step_by(n as usize).for_each(|m| is_composite[m as usize] = true);
+            true
+        })
+        .collect()
+}
+
+/// Number of Miller-Rabin rounds, giving an error probability below 2^-80
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+/// Miller-Rabin probabilistic primality test
+/// https://en.wikipedia.org/wiki/Miller%E2%80%93Rabin_primality_test
+pub fn is_probable_prime(n: &BigUint) -> bool {
+    let two = BigUint::from(2_u32);
+    if *n < two {
+        return false;
+    }
+
+    // Cheap trial division first, which also handles the small primes themselves
+    for p in [2_u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        if (n % p).is_zero() {
+            return *n == BigUint::from(p);
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - 1_u32;
+    let s = n_minus_one.trailing_zeros().unwrap();
+    let d = &n_minus_one >> s;
+
+    (0..MILLER_RABIN_ROUNDS).all(|_| {
+        let mut x = random_in_range(&two, &n_minus_one).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            return true;
+        }
+
+        (1..s).any(|_| {
+            x = &x * &x % n;
+            x == n_minus_one
+        })
+    })
+}
+
+/// Random prime with exactly `bits` bits
+pub fn random_prime(bits: u64) -> BigUint {
+    let mut rng = thread_rng();
+
+    loop {
+        let mut candidate = rng.gen_biguint(bits);
+        candidate.set_bit(bits - 1, true);
+        candidate.set_bit(0, true);
+
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+/// Uniformly random integer in [low, high)
+pub fn random_in_range(low: &BigUint, high: &BigUint) -> BigUint {
+    thread_rng().gen_biguint_range(low, high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(
+            mod_inverse(&BigUint::from(17_u32), &BigUint::from(3120_u32)),
+            Some(BigUint::from(2753_u32))
+        );
+        assert_eq!(mod_inverse(&BigUint::from(6_u32), &BigUint::from(9_u32)), None);
+    }
+
+    #[test]
+    fn test_mod_sqrt() {
+        let p = BigUint::parse_bytes(b"233970423115425145524320034830162017933", 10).unwrap();
+        let a = random_in_range(&BigUint::one(), &p);
+        let square = &a * &a % &p;
+
+        let root = mod_sqrt(&square, &p).unwrap();
+        assert!(root == a || root == &p - &a);
+
+        assert_eq!(mod_sqrt(&BigUint::from(3_u32), &BigUint::from(7_u32)), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        let congruences = [
+            (BigUint::from(2_u32), BigUint::from(3_u32)),
+            (BigUint::from(3_u32), BigUint::from(5_u32)),
+            (BigUint::from(2_u32), BigUint::from(7_u32)),
+        ];
+
+        assert_eq!(crt(&congruences), (BigUint::from(23_u32), BigUint::from(105_u32)));
+    }
+
+    #[test]
+    fn test_is_probable_prime() {
+        assert_eq!(small_primes(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(small_primes(2000)
+            .into_iter()
+            .all(|p| is_probable_prime(&BigUint::from(p))));
+
+        // 2^127 - 1 is a Mersenne prime, 561 a Carmichael number
+        assert!(is_probable_prime(&((BigUint::one() << 127) - 1_u32)));
+        assert!(!is_probable_prime(&BigUint::from(561_u32)));
+        assert!(!is_probable_prime(&((BigUint::one() << 128) + 1_u32)));
+
+        let prime = random_prime(128);
+        assert_eq!(prime.bits(), 128);
+        assert!(is_probable_prime(&prime));
+    }
+
+    #[test]
+    fn test_small_prime_factors() {
+        let n = BigUint::from(2_u32 * 2 * 3 * 11 * 101 * 65537);
+        assert_eq!(small_prime_factors(&n, 1000), vec![(2, 2), (3, 1), (11, 1), (101, 1)]);
+    }
+}
@@ -0,0 +1,228 @@
+//! Implementation of the PCG32 generator (XSH RR 64/32)
+//! https://www.pcg-random.org/download.html
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use rand_core::{impls, Error, RngCore, SeedableRng};
+
+use crate::lattice::{default_delta, lll, Vector};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+/// Increment of the reference PCG32_INITIALIZER
+pub const DEFAULT_INCREMENT: u64 = 0xDA3E_39CB_94B9_5BDB;
+
+/// Bits of the state hidden from every output: the rotation and the 32 bits kept reveal the rest
+const HIDDEN_BITS: u32 = 27;
+
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    /// Same as pcg32_srandom_r(initstate, initseq), choosing the stream from `initseq`
+    pub fn new(initstate: u64, initseq: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: initseq << 1 | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(initstate);
+        rng.step();
+
+        rng
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+
+    fn output(state: u64) -> u32 {
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+
+        xorshifted.rotate_right(rotation)
+    }
+
+    fn next(&mut self) -> u32 {
+        let state = self.state;
+        self.step();
+
+        Self::output(state)
+    }
+
+    /// The top 37 bits of a state giving `output` when its top 5 bits are `rotation`
+    fn known_bits(output: u32, rotation: u32) -> u64 {
+        // Bits 27 to 58 of state ^ (state >> 18), and bits 59 to 63 of the state
+        let xorshifted = (output.rotate_left(rotation) as u64) << 27 | (rotation as u64) << 59;
+
+        // Bits from 46 up are not touched by the shift, the lower ones follow from the top down
+        (HIDDEN_BITS..64).rev().fold(0, |state, bit| {
+            let shifted = if bit + 18 < 64 {
+                (state >> (bit + 18)) & 1
+            } else {
+                0
+            };
+            state | ((xorshifted >> bit) & 1 ^ shifted) << bit
+        })
+    }
+
+    /// LLL reduced basis of the lattice of pairs (a l mod 2^64, l)
+    fn low_bits_lattice() -> Vec<Vector> {
+        let integer = |x: BigInt| BigRational::from_integer(x);
+        let basis = vec![
+            vec![integer(BigInt::one() << 64), BigRational::zero()],
+            vec![integer(BigInt::from(MULTIPLIER)), BigRational::one()],
+        ];
+
+        lll(&basis, &default_delta())
+    }
+
+    /// Low bits l_0 and l_1 of two consecutive states given their top bits h_0 and h_1, which
+    /// satisfy l_1 = a l_0 + t (mod 2^64) with t = a h_0 + c - h_1. Centering both around zero,
+    /// (l_1 - t', l_0) is a point of the lattice close to (-t', 0), found by Babai rounding.
+    fn hidden_bits(lattice: &[Vector], high: [u64; 2], increment: u64) -> Vec<u64> {
+        let center = 1_i64 << (HIDDEN_BITS - 1);
+        let t = MULTIPLIER
+            .wrapping_mul(high[0].wrapping_add(center as u64))
+            .wrapping_add(increment)
+            .wrapping_sub(high[1].wrapping_add(center as u64));
+        let t = BigRational::from_integer(BigInt::from(t));
+
+        // Coordinates of (-t', 0) in the reduced basis
+        let (b0, b1) = (&lattice[0], &lattice[1]);
+        let det = &b0[0] * &b1[1] - &b0[1] * &b1[0];
+        let x0 = (-&t * &b1[1] / &det).round();
+        let x1 = (&t * &b0[1] / &det).round();
+
+        let bound = BigRational::from_integer(BigInt::from(center));
+        (-1..=1)
+            .flat_map(|i| (-1..=1).map(move |j| (i, j)))
+            .filter_map(|(i, j)| {
+                let c0 = &x0 + BigRational::from_integer(BigInt::from(i));
+                let c1 = &x1 + BigRational::from_integer(BigInt::from(j));
+                let l1 = &c0 * &b0[0] + &c1 * &b1[0] + &t;
+                let l0 = &c0 * &b0[1] + &c1 * &b1[1];
+
+                (l0.abs() <= bound && l1.abs() <= bound)
+                    .then(|| (l0 + &bound).to_integer().try_into().ok())
+                    .flatten()
+            })
+            .collect()
+    }
+
+    /// Clones a generator from consecutive outputs and its increment, predicting the outputs
+    /// following them.
+    ///
+    /// Every output reveals the top 37 bits of the state once its rotation is guessed, so guessing
+    /// both rotations of the first two outputs leaves a close vector problem for the 27 low bits.
+    /// At least three outputs are needed to rule out wrong guesses.
+    pub fn clone_from_outputs(outputs: &[u32], increment: u64) -> Option<Self> {
+        if outputs.len() < 3 {
+            return None;
+        }
+
+        let lattice = Self::low_bits_lattice();
+
+        (0..32)
+            .flat_map(|r0| (0..32).map(move |r1| (r0, r1)))
+            .flat_map(|(r0, r1)| {
+                let high = [
+                    Self::known_bits(outputs[0], r0),
+                    Self::known_bits(outputs[1], r1),
+                ];
+                Self::hidden_bits(&lattice, high, increment)
+                    .into_iter()
+                    .map(move |low| high[0] | low)
+            })
+            .find_map(|state| {
+                let mut rng = Self { state, increment };
+                outputs
+                    .iter()
+                    .all(|&output| rng.next() == output)
+                    .then(|| rng)
+            })
+    }
+}
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        self.next()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Pcg32 {
+    type Seed = [u8; 16];
+
+    /// initstate and initseq in little endian
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut words = [0; 2];
+        for (word, chunk) in words.iter_mut().zip(seed.chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            *word = u64::from_le_bytes(bytes);
+        }
+
+        Self::new(words[0], words[1])
+    }
+
+    /// Seeds the state on the default stream, instead of expanding the seed with PCG32
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed, DEFAULT_INCREMENT >> 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_pcg32() {
+        // Output of pcg32-demo, seeded with pcg32_srandom_r(&rng, 42, 54)
+        let mut rng = Pcg32::new(42, 54);
+        let outputs = (0..6).map(|_| rng.next_u32()).collect::<Vec<_>>();
+
+        assert_eq!(
+            outputs,
+            [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]
+        );
+    }
+
+    #[test]
+    fn test_pcg32_known_bits() {
+        let state = thread_rng().gen::<u64>();
+        let high = Pcg32::known_bits(Pcg32::output(state), (state >> 59) as u32);
+
+        assert_eq!(high, state >> HIDDEN_BITS << HIDDEN_BITS);
+    }
+
+    #[test]
+    fn test_pcg32_clone() {
+        let mut rng = Pcg32::seed_from_u64(thread_rng().gen());
+        let outputs = (0..4).map(|_| rng.next_u32()).collect::<Vec<_>>();
+
+        let mut cloned = Pcg32::clone_from_outputs(&outputs, DEFAULT_INCREMENT).unwrap();
+
+        for _ in 0..1000 {
+            assert_eq!(cloned.next_u32(), rng.next_u32());
+        }
+    }
+}
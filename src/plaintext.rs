@@ -0,0 +1,290 @@
+//! Classifiers telling plausible plaintexts from the garbage of wrong keys, beyond ASCII English
+
+use crate::challenges::set1::challenge3::{CombinedScorer, PatternScorer};
+use crate::language_model::LanguageModel;
+
+/// Judges a candidate decryption
+pub trait PlaintextClassifier {
+    /// How plausible `plaintext` is, None when it is ruled out
+    fn classify(&self, plaintext: &[u8]) -> Option<u16>;
+
+    /// How plausible a column of a plaintext is, its bytes taken a key length apart as when
+    /// breaking a repeating key. Only classifiers judging the bytes a plaintext holds, wherever
+    /// they are, can tell.
+    fn classify_column(&self, column: &[u8]) -> Option<u16> {
+        self.classify(column)
+    }
+}
+
+/// Text scorers accept any UTF-8, so texts in other languages are not ruled out
+impl<T: PatternScorer> PlaintextClassifier for T {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        let text = std::str::from_utf8(plaintext).ok()?;
+
+        self.get_score(text).ok()
+    }
+}
+
+/// Valid UTF-8, scored by its share of printable characters
+pub struct Utf8Classifier;
+
+impl PlaintextClassifier for Utf8Classifier {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        let text = std::str::from_utf8(plaintext).ok()?;
+        let (printable, total) = text.chars().fold((0, 0), |(printable, total), c| {
+            let is_printable = !c.is_control() || c.is_whitespace();
+            (printable + is_printable as usize, total + 1)
+        });
+
+        (total > 0).then(|| (u16::MAX as usize * printable / total) as u16)
+    }
+}
+
+/// UTF-8 text in the language of a model
+pub struct LanguageClassifier {
+    language: String,
+    scorer: CombinedScorer,
+}
+
+impl LanguageClassifier {
+    pub fn new(model: &LanguageModel) -> Self {
+        Self {
+            language: model.language.clone(),
+            scorer: CombinedScorer::from_model(model),
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+}
+
+impl PlaintextClassifier for LanguageClassifier {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        let text = std::str::from_utf8(plaintext).ok()?;
+
+        self.scorer.get_score(text).ok()
+    }
+}
+
+/// The language of the classifier scoring `plaintext` the highest
+pub fn detect_language<'a>(
+    classifiers: &'a [LanguageClassifier],
+    plaintext: &[u8],
+) -> Option<&'a str> {
+    classifiers
+        .iter()
+        .filter_map(|classifier| Some((classifier.classify(plaintext)?, classifier.language())))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, language)| language)
+}
+
+/// Shannon entropy in bits per byte
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0_usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+
+    let length = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let probability = count as f64 / length;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+/// Structured binary such as headers, tables or executables: its entropy stays below that of
+/// compressed or encrypted data. The entropy of a byte distribution does not change under xor
+/// with a single byte, so candidates are told apart by their share of zero bytes, the padding
+/// dominating structured formats.
+pub struct EntropyClassifier {
+    max_entropy: f64,
+}
+
+impl EntropyClassifier {
+    /// Compressed and encrypted data get close to the maximum of 8 bits per byte
+    pub const DEFAULT_MAX_ENTROPY: f64 = 7.0;
+
+    pub fn new(max_entropy: f64) -> Self {
+        Self { max_entropy }
+    }
+}
+
+impl Default for EntropyClassifier {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_ENTROPY)
+    }
+}
+
+impl PlaintextClassifier for EntropyClassifier {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        let entropy = entropy(plaintext);
+        if plaintext.is_empty() || entropy > self.max_entropy {
+            return None;
+        }
+
+        let zeros = plaintext.iter().filter(|&&byte| byte == 0).count() as f64;
+        let score = (1.0 - entropy / 8.0 + zeros / plaintext.len() as f64) / 2.0;
+
+        Some((score * u16::MAX as f64) as u16)
+    }
+}
+
+/// Signatures at the start of common file formats
+const FILE_MAGIC: &[(&str, &[u8])] = &[
+    ("png", b"\x89PNG\r\n\x1a\n"),
+    ("jpeg", b"\xff\xd8\xff"),
+    ("gif", b"GIF87a"),
+    ("gif", b"GIF89a"),
+    ("pdf", b"%PDF-"),
+    ("zip", b"PK\x03\x04"),
+    ("gzip", b"\x1f\x8b\x08"),
+    ("bzip2", b"BZh"),
+    ("elf", b"\x7fELF"),
+];
+
+/// Plaintexts starting with the signature of a known file format
+pub struct MagicClassifier;
+
+impl MagicClassifier {
+    pub fn file_type(plaintext: &[u8]) -> Option<&'static str> {
+        FILE_MAGIC
+            .iter()
+            .find(|(_, magic)| plaintext.starts_with(magic))
+            .map(|(file_type, _)| *file_type)
+    }
+}
+
+impl PlaintextClassifier for MagicClassifier {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        Self::file_type(plaintext).map(|_| u16::MAX)
+    }
+
+    /// A column does not start where the plaintext does, so signatures say nothing about it
+    fn classify_column(&self, _column: &[u8]) -> Option<u16> {
+        None
+    }
+}
+
+/// Takes the best score of several classifiers, accepting what any of them accepts
+#[derive(Default)]
+pub struct ClassifierSet {
+    classifiers: Vec<Box<dyn PlaintextClassifier + Send + Sync>>,
+}
+
+impl ClassifierSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<C: PlaintextClassifier + Send + Sync + 'static>(mut self, classifier: C) -> Self {
+        self.classifiers.push(Box::new(classifier));
+        self
+    }
+}
+
+impl PlaintextClassifier for ClassifierSet {
+    fn classify(&self, plaintext: &[u8]) -> Option<u16> {
+        self.classifiers
+            .iter()
+            .filter_map(|classifier| classifier.classify(plaintext))
+            .max()
+    }
+
+    fn classify_column(&self, column: &[u8]) -> Option<u16> {
+        self.classifiers
+            .iter()
+            .filter_map(|classifier| classifier.classify_column(column))
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenges::set1::challenge3::break_cipher_with;
+    use crate::language_model::LanguageModelBuilder;
+    use rand::{thread_rng, Rng};
+
+    const GERMAN: &str = "Über allen Gipfeln ist Ruh, in allen Wipfeln spürest du kaum \
+        einen Hauch; die Vögelein schweigen im Walde. Warte nur, balde ruhest du auch. Wer \
+        reitet so spät durch Nacht und Wind? Es ist der Vater mit seinem Kind; er hat den \
+        Knaben wohl in dem Arm, er faßt ihn sicher, er hält ihn warm.";
+
+    #[test]
+    fn test_utf8_classifier() {
+        assert_eq!(Utf8Classifier.classify("Grüße".as_bytes()), Some(u16::MAX));
+        assert!(Utf8Classifier.classify(b"ab\x01\x02").unwrap() < u16::MAX);
+        assert_eq!(Utf8Classifier.classify(b"\xc3\x28"), None);
+        assert_eq!(Utf8Classifier.classify(b""), None);
+    }
+
+    #[test]
+    fn test_detect_language() {
+        let german = LanguageModelBuilder::new("de").add_text(GERMAN).build();
+        let classifiers = [
+            LanguageClassifier::new(&LanguageModel::english().unwrap()),
+            LanguageClassifier::new(&german),
+        ];
+
+        let detected = detect_language(&classifiers, "Es ist der Vater".as_bytes());
+        assert_eq!(detected, Some("de"));
+
+        let detected = detect_language(&classifiers, "It is the father".as_bytes());
+        assert_eq!(detected, Some("en"));
+    }
+
+    #[test]
+    fn test_break_cipher_german() {
+        let german = LanguageModelBuilder::new("de").add_text(GERMAN).build();
+        let plaintext = "Wer reitet so spät durch Nacht und Wind?";
+        let cipher = hex::encode(plaintext.bytes().map(|b| b ^ 0x5A).collect::<Vec<_>>());
+
+        let deciphered = break_cipher_with(&LanguageClassifier::new(&german), &cipher).unwrap();
+
+        assert_eq!(deciphered.key, 0x5A);
+        assert_eq!(deciphered.deciphered, plaintext);
+    }
+
+    #[test]
+    fn test_entropy_classifier() {
+        let mut random = [0_u8; 1024];
+        thread_rng().fill(&mut random[..]);
+        let mut structured = [0_u8; 1024];
+        structured[..64].copy_from_slice(&random[..64]);
+
+        assert!(entropy(&random) > EntropyClassifier::DEFAULT_MAX_ENTROPY);
+        assert_eq!(EntropyClassifier::default().classify(&random), None);
+
+        let xored = structured
+            .iter()
+            .map(|byte| byte ^ 0x42)
+            .collect::<Vec<_>>();
+        let classifier = EntropyClassifier::default();
+        assert!(classifier.classify(&structured).unwrap() > classifier.classify(&xored).unwrap());
+    }
+
+    #[test]
+    fn test_magic_classifier() {
+        assert_eq!(
+            MagicClassifier::file_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some("png")
+        );
+        assert_eq!(MagicClassifier::file_type(b"%PDF-1.7"), Some("pdf"));
+        assert_eq!(MagicClassifier.classify(b"PNG"), None);
+
+        let classifiers = ClassifierSet::new()
+            .with(Utf8Classifier)
+            .with(MagicClassifier);
+        assert_eq!(
+            classifiers.classify(b"\x1f\x8b\x08\x00\xff"),
+            Some(u16::MAX)
+        );
+        assert_eq!(classifiers.classify(b"\x1f\x8b\x09\x00\xff"), None);
+        assert_eq!(classifiers.classify_column(b"\x1f\x8b\x08\x00\xff"), None);
+    }
+}
@@ -0,0 +1,82 @@
+//! Implementation of the RC4 stream cipher
+//! https://en.wikipedia.org/wiki/RC4
+
+pub struct Rc4 {
+    s: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    /// Key-scheduling algorithm (KSA)
+    pub fn new(key: &[u8]) -> Self {
+        assert!(!key.is_empty() && key.len() <= 256, "Invalid RC4 key length");
+
+        let mut s = [0_u8; 256];
+        s.iter_mut().enumerate().for_each(|(i, x)| *x = i as u8);
+
+        let mut j = 0_u8;
+        for i in 0..256 {
+            j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+            s.swap(i, j as usize);
+        }
+
+        Self { s, i: 0, j: 0 }
+    }
+
+    /// Pseudo-random generation algorithm (PRGA)
+    pub fn next_byte(&mut self) -> u8 {
+        self.i = self.i.wrapping_add(1);
+        self.j = self.j.wrapping_add(self.s[self.i as usize]);
+        self.s.swap(self.i as usize, self.j as usize);
+
+        let index = self.s[self.i as usize].wrapping_add(self.s[self.j as usize]);
+        self.s[index as usize]
+    }
+
+    pub fn keystream(&mut self, size: usize) -> Vec<u8> {
+        (0..size).map(|_| self.next_byte()).collect()
+    }
+
+    pub fn apply_keystream(&mut self, buffer: &[u8]) -> Vec<u8> {
+        buffer.iter().map(|x| x ^ self.next_byte()).collect()
+    }
+
+    pub fn encrypt(plain_buffer: &[u8], key: &[u8]) -> Vec<u8> {
+        Self::new(key).apply_keystream(plain_buffer)
+    }
+
+    pub fn decrypt(cipher_buffer: &[u8], key: &[u8]) -> Vec<u8> {
+        Self::new(key).apply_keystream(cipher_buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rc4_test_vectors() {
+        // Test vectors from https://en.wikipedia.org/wiki/RC4#Test_vectors
+        assert_eq!(
+            Rc4::encrypt(b"Plaintext", b"Key"),
+            hex::decode("bbf316e8d940af0ad3").unwrap()
+        );
+        assert_eq!(
+            Rc4::encrypt(b"pedia", b"Wiki"),
+            hex::decode("1021bf0420").unwrap()
+        );
+        assert_eq!(
+            Rc4::encrypt(b"Attack at dawn", b"Secret"),
+            hex::decode("45a01f645fc35b383552544b9bf5").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rc4_encrypt_decrypt() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plain_text = "HALLO LEGO!!".as_bytes();
+
+        assert_eq!(Rc4::decrypt(&Rc4::encrypt(plain_text, key), key), plain_text);
+    }
+}
@@ -0,0 +1,99 @@
+//! Textbook RSA, signatures are computed directly over the SHA-256 hash without padding
+//! https://en.wikipedia.org/wiki/RSA_(cryptosystem)
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use openssl::sha::sha256;
+
+use crate::number_theory::{mod_inverse, random_prime};
+
+pub const RSA_PUBLIC_EXPONENT: u32 = 65537;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    pub n: BigUint,
+    pub e: BigUint,
+}
+
+impl RsaPublicKey {
+    pub fn encrypt(&self, plain: &BigUint) -> BigUint {
+        plain.modpow(&self.e, &self.n)
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &BigUint) -> bool {
+        *signature < self.n && self.encrypt(signature) == message_representative(message) % &self.n
+    }
+}
+
+pub struct RsaKeyPair {
+    pub public: RsaPublicKey,
+    d: BigUint,
+}
+
+impl RsaKeyPair {
+    /// Generates a key with a modulus of `bits` bits and e = 65537
+    pub fn generate(bits: u64) -> Self {
+        let e = BigUint::from(RSA_PUBLIC_EXPONENT);
+
+        loop {
+            let p = random_prime(bits / 2);
+            let q = random_prime(bits - bits / 2);
+            if p == q {
+                continue;
+            }
+
+            if let Some(key_pair) = Self::from_primes(&p, &q, e.clone()) {
+                if key_pair.public.n.bits() == bits {
+                    return key_pair;
+                }
+            }
+        }
+    }
+
+    /// Fails when e is not invertible modulo lcm(p - 1, q - 1)
+    pub fn from_primes(p: &BigUint, q: &BigUint, e: BigUint) -> Option<Self> {
+        let lambda = (p - 1_u32).lcm(&(q - 1_u32));
+        let d = mod_inverse(&e, &lambda)?;
+
+        Some(Self {
+            public: RsaPublicKey { n: p * q, e },
+            d,
+        })
+    }
+
+    pub fn decrypt(&self, cipher: &BigUint) -> BigUint {
+        cipher.modpow(&self.d, &self.public.n)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> BigUint {
+        self.decrypt(&(message_representative(message) % &self.public.n))
+    }
+}
+
+/// SHA-256 of the message as an integer
+pub fn message_representative(message: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(&sha256(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsa() {
+        let key_pair = RsaKeyPair::generate(512);
+        let plain = BigUint::from(42_u32);
+
+        assert_eq!(key_pair.public.n.bits(), 512);
+        assert_eq!(key_pair.decrypt(&key_pair.public.encrypt(&plain)), plain);
+    }
+
+    #[test]
+    fn test_rsa_signature() {
+        let key_pair = RsaKeyPair::generate(512);
+        let signature = key_pair.sign(b"hi mom");
+
+        assert!(key_pair.public.verify(b"hi mom", &signature));
+        assert!(!key_pair.public.verify(b"hi dad", &signature));
+    }
+}
@@ -43,7 +43,7 @@ impl UnicodeUtils for [u8] {
 
     fn xor_all<'a, I: IntoIterator<Item = &'a u8>>(&self, other: I) -> Vec<u8> {
         self.iter()
-            .zip(other.into_iter())
+            .zip(other)
             .map(|(x, y)| x ^ y)
             .collect::<Vec<_>>()
     }
@@ -81,8 +81,14 @@ impl Base64 for str {
 }
 
 pub fn generate_random_bytes(size: Option<usize>) -> Vec<u8> {
-    let mut rng = thread_rng();
+    generate_random_bytes_with_rng(&mut thread_rng(), size)
+}
 
+/// Same as `generate_random_bytes`, drawing from the given generator so the output can be reproduced
+pub fn generate_random_bytes_with_rng<R: Rng + ?Sized>(
+    rng: &mut R,
+    size: Option<usize>,
+) -> Vec<u8> {
     let size = match size {
         Some(size) => size,
         None => rng.gen_range(0..256),
@@ -99,7 +105,7 @@ pub fn read_lines_from_file(file_path: &str) -> impl IntoIterator<Item = String>
     let file = File::open(file_path).expect("Failed to open data file.");
     let reader = BufReader::new(file);
 
-    reader.lines().flatten()
+    reader.lines().map_while(Result::ok)
 }
 
 
@@ -0,0 +1,156 @@
+//! Implementation of the xorshift128+ generator
+//! https://en.wikipedia.org/wiki/Xorshift#xorshift+
+
+use rand_core::{impls, Error, RngCore, SeedableRng};
+
+use crate::gf2::{Gf2Matrix, Gf2Vector};
+
+pub struct Xorshift128Plus {
+    s: [u64; 2],
+}
+
+impl Xorshift128Plus {
+    /// Outputs needed to recover the state
+    pub const CLONE_OUTPUTS: usize = 128;
+
+    pub fn new(s0: u64, s1: u64) -> Self {
+        assert!(s0 != 0 || s1 != 0, "The state must not be all zero");
+
+        Self { s: [s0, s1] }
+    }
+
+    /// The linear part of the generator, moving the state forward
+    fn step(&mut self) {
+        let mut s1 = self.s[0];
+        let s0 = self.s[1];
+        self.s[0] = s0;
+        s1 ^= s1 << 23;
+        self.s[1] = s1 ^ s0 ^ (s1 >> 18) ^ (s0 >> 5);
+    }
+
+    fn next(&mut self) -> u64 {
+        let result = self.s[0].wrapping_add(self.s[1]);
+        self.step();
+
+        result
+    }
+
+    /// Clones a generator from consecutive outputs, predicting the ones following them.
+    ///
+    /// The addition is the only non-linear part, but the lowest bit of a sum is the xor of the
+    /// lowest bits of its terms. Every output then gives a linear equation on the 128 state bits,
+    /// and as the generator has full period, `CLONE_OUTPUTS` outputs determine the state.
+    pub fn clone_from_outputs(outputs: &[u64]) -> Option<Self> {
+        let low_bits = |mut rng: Self| {
+            let bits = outputs
+                .iter()
+                .map(|_| {
+                    let bit = (rng.s[0] ^ rng.s[1]) & 1 == 1;
+                    rng.step();
+                    bit
+                })
+                .collect::<Vec<_>>();
+
+            Gf2Vector::from_bits(&bits)
+        };
+
+        // The low bits produced from every single state bit give one column of the system
+        let columns = (0..128)
+            .map(|i| {
+                let mut s = [0; 2];
+                s[i / 64] = 1 << (i % 64);
+                low_bits(Self { s })
+            })
+            .collect::<Vec<_>>();
+        let known = outputs
+            .iter()
+            .map(|&output| output & 1 == 1)
+            .collect::<Vec<_>>();
+
+        let system = Gf2Matrix::from_columns(outputs.len(), &columns);
+        let solution = system.solve(&Gf2Vector::from_bits(&known))?;
+
+        let mut s = [0; 2];
+        for i in solution.ones() {
+            s[i / 64] |= 1 << (i % 64);
+        }
+        if s == [0; 2] {
+            return None;
+        }
+
+        // Replays the outputs, which also catches an ambiguous solution
+        let mut rng = Self { s };
+        outputs
+            .iter()
+            .all(|&output| rng.next() == output)
+            .then(|| rng)
+    }
+}
+
+impl RngCore for Xorshift128Plus {
+    /// The high half of the next output, the lowest bits being the weakest
+    fn next_u32(&mut self) -> u32 {
+        (self.next() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Xorshift128Plus {
+    type Seed = [u8; 16];
+
+    /// The state words in little endian, an all zero seed being replaced by `seed_from_u64(0)`
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut s = [0; 2];
+        for (word, chunk) in s.iter_mut().zip(seed.chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            *word = u64::from_le_bytes(bytes);
+        }
+
+        if s == [0; 2] {
+            return Self::seed_from_u64(0);
+        }
+        Self::new(s[0], s[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_xorshift128plus() {
+        let mut rng = Xorshift128Plus::new(1, 2);
+
+        assert_eq!(rng.next_u64(), 3);
+        // s = (2, 0x800001 ^ 2 ^ (0x800001 >> 18))
+        assert_eq!(rng.next_u64(), 2 + 0x80_0023);
+    }
+
+    #[test]
+    fn test_xorshift128plus_clone() {
+        let mut rng = Xorshift128Plus::from_seed(thread_rng().gen());
+        let outputs = (0..Xorshift128Plus::CLONE_OUTPUTS)
+            .map(|_| rng.next_u64())
+            .collect::<Vec<_>>();
+
+        let mut cloned = Xorshift128Plus::clone_from_outputs(&outputs).unwrap();
+
+        for _ in 0..1000 {
+            assert_eq!(cloned.next_u64(), rng.next_u64());
+        }
+    }
+}